@@ -2,10 +2,11 @@ extern crate textflow;
 
 use textflow::columns;
 use textflow::Alignment::*;
+use textflow::Gutters;
 use textflow::Layout;
 use textflow::Spacing::*;
 
-fn main() -> Result<(), String> {
+fn main() -> Result<(), textflow::Error> {
     let text1 = "The November sky above was blue.";
     let text2 =
         "It was mid-November, the time when the season of changing leaves was drawing to a close;";
@@ -17,13 +18,13 @@ fn main() -> Result<(), String> {
     println!("BASIC ======================================================\n");
     println!(
         "{}",
-        columns([text1, text2, text3], BETWEEN, Layout::default(), 60)
+        columns([text1, text2, text3], BETWEEN, &Layout::default(), 60)
     );
 
     println!("\n\nMIRRORED =================================================\n");
     println!(
         "{}",
-        columns([roles, actors], BETWEEN, Layout::from_pattern("> <")?, 60)
+        columns([roles, actors], BETWEEN, &Layout::from_pattern("> <")?, 60)
     );
 
     println!("\n\nFIXED WIDTH COLUMN =========================================\n");
@@ -32,7 +33,7 @@ fn main() -> Result<(), String> {
         columns(
             [text1, text2, text3],
             AROUND,
-            Layout::from_pattern("16 *")?,
+            &Layout::from_pattern("16 *")?,
             60
         )
     );
@@ -43,7 +44,18 @@ fn main() -> Result<(), String> {
         columns(
             [text1, text2, text3],
             BETWEEN,
-            Layout::from_pattern("=- ^15* >--")?,
+            &Layout::from_pattern("=- ^15* >--")?,
+            60
+        )
+    );
+
+    println!("\n\nCUSTOM GUTTERS =============================================\n");
+    println!(
+        "{}",
+        columns(
+            [text1, text2, text3],
+            Gutters::new().left("┃ ").between(" │ ").right(" ┃"),
+            &Layout::default(),
             60
         )
     );
@@ -54,7 +66,10 @@ fn main() -> Result<(), String> {
         .repeat()
         .fractional(2, RIGHT);
     println!("\n\nSAME LAYOUT, DIFFERENT CONSTRUCTOR =========================\n");
-    println!("{}", columns([text1, text2, text3], BETWEEN, my_layout, 60));
+    println!(
+        "{}",
+        columns([text1, text2, text3], BETWEEN, &my_layout, 60)
+    );
 
     Ok(())
 }
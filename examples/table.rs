@@ -0,0 +1,28 @@
+extern crate textflow;
+
+use textflow::table;
+use textflow::Layout;
+
+fn main() -> Result<(), textflow::Error> {
+    let rows = [
+        vec!["Character", "Ability", "Level"],
+        vec!["Tōma Kamijō", "Imagine Breaker", "0"],
+        vec!["Accelerator", "Vector manipulation", "5"],
+        vec![
+            "Mikoto Misaka",
+            "Electromaster, can shoot coins at three times the speed of sound",
+            "5",
+        ],
+    ];
+
+    println!("WITH HEADER ================================================\n");
+    println!(
+        "{}",
+        table(rows.clone(), true, &Layout::from_pattern("- -- ^5")?, 60)
+    );
+
+    println!("\n\nWITHOUT HEADER =============================================\n");
+    println!("{}", table(rows, false, &Layout::from_pattern("=*")?, 60));
+
+    Ok(())
+}
@@ -1,21 +1,52 @@
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::ansi;
 use crate::utils::*;
 
+use crate::layout::Column;
 use crate::Alignment;
-use unicode_width::UnicodeWidthStr as UniW;
+use crate::Direction;
+use crate::Error;
+use crate::Gutters;
+use crate::Justification;
+use crate::LastLine;
+use crate::Lines;
+use crate::Overflow;
+use crate::UnicodeWidth;
+use crate::WidthMeasure;
 
 /// Wraps and aligns text.
 ///
-/// `width_or_options` can either be an integer or [textwrap::Options],
-///  see the documentation of `textwrap` for more information.
+/// `width_or_options` can either be an integer, [textwrap::Options]
+/// or [Options](crate::Options), see the documentation of `textwrap`
+/// for more information. Lines that are still too wide after wrapping
+/// are handled according to [Options::overflow](crate::Options::overflow).
 ///
-/// There are four alignment modes :
+/// There are seven alignment modes :
 /// * `LEFT` doesn't modifiy the text, so it ends up left-aligned
+/// * `START` and `END` are the same as `LEFT` and `RIGHT` respectively,
+///   or the opposite if the direction is right-to-left
+///   (see [Options::direction](crate::Options::direction))
 /// * `RIGHT` pad each line with spaces so that the text is right-aligned
-///           to the width specified by `width_or_options`
+///   to the width specified by `width_or_options`
 /// * `CENTER` pad each line with spaces so that the text is centered inside
-///            the width specified by `width_or_options`
-/// * `JUSTIFY` expand spaces so that each line (except the last one) are
-///             filling the width specified by `width_or_options`
+///   the width specified by `width_or_options`
+/// * `ANCHOR` lines up the lines on the first occurrence of a character,
+///   like a decimal separator, and pads them so that the block of text is
+///   right-aligned. Lines without this character are placed as if it came
+///   right after them, so integers line up with decimal numbers. If the lined
+///   up block is wider than the width, each line is right-aligned instead
+/// * `JUSTIFY` expand spaces so that each line (except the last line of
+///   each paragraph) are filling the width specified by `width_or_options`, the extra spaces
+///   being placed according to [Options::justification](crate::Options::justification)
+///   (the last line is aligned according to [Options::last_line](crate::Options::last_line))
+///
+/// ANSI escape sequences (colors, hyperlinks ...) don't count towards the
+/// width of the text, and styles and hyperlinks are closed at the end of
+/// each line and reopened at the start of the next one so that they don't
+/// leak into the padding. Tabs are expanded before wrapping, see [Options::tabs](crate::Options::tabs).
+/// The width of the characters is given by [Options::measure](crate::Options::measure).
+/// The padding can be drawn with a pattern instead of spaces, see [Options::fill](crate::Options::fill).
 ///
 /// # Examples
 ///
@@ -36,163 +67,877 @@ use unicode_width::UnicodeWidthStr as UniW;
 /// ```
 ///
 /// The crate also contains [an example](https://github.com/louisdevie/textflow/blob/main/examples/alignment.rs).
+///
+/// # Panics
+///
+/// This function panics if a line is too wide and the overflow policy is
+/// [Overflow::ERROR], or if the text contains a tab and the tab policy is
+/// [Tabs::ERROR](crate::Tabs::ERROR). Use [try_align()] to handle these
+/// errors instead.
 pub fn align<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit, TextwrapOptions>(
     text: &str,
     alignment: Alignment,
     width_or_options: TextwrapOptions,
 ) -> String
 where
-    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm,
-    TextwrapWordSep: textwrap::word_separators::WordSeparator,
-    TextwrapWordSplit: textwrap::word_splitters::WordSplitter,
-    TextwrapOptions: Into<textwrap::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+{
+    try_align(text, alignment, width_or_options).unwrap()
+}
+
+/// Wraps and aligns text, without panicking.
+///
+/// This is the same as [align()], but returns an [Error::LineTooWide]
+/// if a line doesn't fit and the overflow policy is [Overflow::ERROR],
+/// or an [Error::TabCharacter] if the text contains a tab and the tab
+/// policy is [Tabs::ERROR](crate::Tabs::ERROR).
+///
+/// # Examples
+///
+/// ```
+/// use textflow::{try_align, Error, Options, Overflow};
+/// use textflow::Alignment::*;
+///
+/// let options = Options::new(8).break_words(false).overflow(Overflow::ERROR);
+///
+/// assert_eq!(
+///     try_align("the incomprehensibilities", LEFT, options),
+///     Err(Error::LineTooWide { width: 8, actual: 21 })
+/// );
+/// ```
+pub fn try_align<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit, TextwrapOptions>(
+    text: &str,
+    alignment: Alignment,
+    width_or_options: TextwrapOptions,
+) -> Result<String, Error>
+where
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+{
+    let lines: Vec<String> =
+        align_lines(text, alignment, width_or_options).collect::<Result<_, _>>()?;
+
+    // no line feed at the end
+    return Ok(lines.join("\n"));
+}
+
+/// Wraps and aligns text, one line at a time.
+///
+/// This is the same as [align()], but the lines are aligned as they are
+/// requested from the returned [Lines]. They can also be written directly
+/// into a sink with [Lines::write_to] or [Lines::write_io].
+///
+/// # Examples
+///
+/// ```
+/// use textflow::align_lines;
+/// use textflow::Alignment::*;
+///
+/// let mut output = String::new();
+/// align_lines("textflow:\na small extension for textwrap.", CENTER, 20)
+///     .write_to(&mut output)
+///     .unwrap();
+///
+/// assert_eq!(output, "     textflow:      \n a small extension  \n   for textwrap.    \n");
+/// ```
+pub fn align_lines<'a, 't, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit, TextwrapOptions>(
+    text: &'t str,
+    alignment: Alignment,
+    width_or_options: TextwrapOptions,
+) -> Lines<'t>
+where
+    'a: 't,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
 {
     let options = width_or_options.into();
 
-    // copy the width before passing the options
-    // to `wrap` because it consumes it
-    let width = options.width;
+    let column = Column {
+        width: options.wrap.width,
+        alignment,
+        ..Default::default()
+    };
+    let settings = options.line_settings();
 
-    let wrapped = textwrap::wrap(text, options);
+    return match wrap_text(text, &options.wrap, options.tabs, options.measure) {
+        Ok(wrapped) => Lines::new(vec![wrapped], vec![column], Gutters::new(), settings),
+        Err(error) => Lines::failed(error),
+    };
+}
 
-    let mut wrapped_and_aligned = String::new();
+// the settings that apply to every line, copied from the options
+#[derive(Copy, Clone)]
+pub struct LineSettings<'a> {
+    pub overflow: Overflow,
+    pub justification: Justification,
+    pub last_line: LastLine,
+    pub direction: Direction,
+    pub measure: &'a dyn WidthMeasure,
+    pub fill: &'a str,
+}
 
-    for (i, line) in wrapped.iter().enumerate() {
-        let last_line = i == wrapped.len() - 1;
-        wrapped_and_aligned.push_str(&align_line(line, width, alignment, last_line));
-        // no line feed at the end
-        if !last_line {
-            wrapped_and_aligned.push('\n');
+impl Default for LineSettings<'_> {
+    fn default() -> Self {
+        LineSettings {
+            overflow: Overflow::default(),
+            justification: Justification::default(),
+            last_line: LastLine::default(),
+            direction: Direction::default(),
+            measure: &UnicodeWidth,
+            fill: " ",
         }
     }
+}
 
-    return wrapped_and_aligned;
+impl<'a> LineSettings<'a> {
+    // the settings of the lines of a column, which may have its own fill pattern
+    pub fn for_column(self, column: &'a Column) -> Self {
+        LineSettings {
+            fill: column.fill.as_deref().unwrap_or(self.fill),
+            ..self
+        }
+    }
+}
+
+impl From<Overflow> for LineSettings<'_> {
+    fn from(overflow: Overflow) -> Self {
+        LineSettings {
+            overflow,
+            ..Default::default()
+        }
+    }
 }
 
 // real deal
-pub fn align_line(line: &str, width: usize, alignment: Alignment, last: bool) -> String {
-    let remaining = width - UniW::width(line);
+// (`index` is the position of the line in its block of text)
+pub fn align_line(
+    line: &str,
+    width: usize,
+    alignment: Alignment,
+    last: bool,
+    index: usize,
+    settings: LineSettings,
+) -> Result<String, Error> {
+    let line_width = ansi::display_width(line, settings.measure);
+    if line_width > width {
+        return overflow_line(line, width, line_width, settings);
+    }
+
+    let remaining = width - line_width;
+    // don't let the style of the line leak into the padding
+    let line = &ansi::close_style(line, "");
+
+    // the last line of a justified block has its own alignment
+    let alignment = if alignment == Alignment::JUSTIFY && last {
+        settings.last_line.alignment(line_width, width)
+    } else {
+        alignment
+    };
+
+    // the logical alignments depend on the direction of the text
+    let alignment = match (alignment, settings.direction) {
+        (Alignment::START, Direction::LTR) | (Alignment::END, Direction::RTL) => Alignment::LEFT,
+        (Alignment::START, Direction::RTL) | (Alignment::END, Direction::LTR) => Alignment::RIGHT,
+        (alignment, _) => alignment,
+    };
 
-    match alignment {
+    // the padding before or after the line (`start` columns into it),
+    // a space is kept next to the text and empty lines are left blank
+    let fill = if line_width == 0 { " " } else { settings.fill };
+    let pad_before = |len: usize| match len {
+        0 => String::new(),
+        _ => fill_pattern(fill, 0, len - 1, settings.measure) + " ",
+    };
+    let pad_after = |len: usize, start: usize| match len {
+        0 => String::new(),
+        _ => String::from(" ") + &fill_pattern(fill, start + 1, len - 1, settings.measure),
+    };
+
+    let aligned = match alignment {
         // pad at the end (useful for `columns`)
-        Alignment::LEFT => String::from(line) + &" ".repeat(remaining),
+        Alignment::LEFT => String::from(line) + &pad_after(remaining, line_width),
 
         // pad at the start
-        Alignment::RIGHT => " ".repeat(remaining) + line,
+        Alignment::RIGHT => pad_before(remaining) + line,
+
+        // already replaced with `LEFT` or `RIGHT`
+        Alignment::START | Alignment::END => unreachable!(),
 
         // pad each side (again, the padding at the end is useful for `columns`)
         Alignment::CENTER => {
             let before = remaining / 2;
-            " ".repeat(before) + line + &" ".repeat(remaining - before)
+            pad_before(before) + line + &pad_after(remaining - before, before + line_width)
         }
 
+        // alone, a line is lined up on its own anchor, so it is simply
+        // right-aligned (`Lines` takes care of the blocks of lines)
+        Alignment::ANCHOR(_) => pad_before(remaining) + line,
+
         // now onto the complicated stuff
         Alignment::JUSTIFY => {
-            if last {
-                // the last line doesn't get justified
-                String::from(line) + &" ".repeat(remaining)
+            let mut words: Vec<&str> = line.split(" ").collect();
+            // distribute spaces
+            let spaces = spread_spaces(
+                &words,
+                words.len() + remaining - 1,
+                index,
+                settings.justification,
+            );
+
+            // the first word is treated separately
+            let mut aligned = if !words.is_empty() {
+                // `remove(0)` will panics if the vector is empty ...
+                String::from(words.remove(0))
             } else {
-                let mut words: Vec<&str> = line.split(" ").collect();
-                // distribute spaces
-                let spaces = split_evenly(words.len() + remaining - 1, words.len() - 1);
-
-                // the first word is treated separately
-                let mut aligned = if words.len() != 0 {
-                    // `remove(0)` will panics if the vector is empty ...
-                    String::from(words.remove(0))
-                } else {
-                    // ... it means the line is empty so we return an empty string
-                    String::new()
-                };
-                if words.len() == 0 {
-                    // only one word
-                    aligned.push_str(&" ".repeat(remaining));
+                // ... it means the line is empty so we return an empty string
+                String::new()
+            };
+            if words.is_empty() {
+                // only one word
+                aligned.push_str(&pad_after(remaining, line_width));
+            } else {
+                for (word, spacing) in words.iter().zip(spaces) {
+                    aligned.push_str(&" ".repeat(spacing));
+                    aligned.push_str(word);
+                }
+            }
+
+            aligned
+        }
+    };
+
+    return Ok(aligned);
+}
+
+// repeats a pattern over `width` columns, starting `start` columns into
+// the line so that it lines up with the same pattern on other lines,
+// and puts spaces where a part of the pattern doesn't fit. The escape
+// sequences of the pattern are kept whole and don't count as columns
+fn fill_pattern(pattern: &str, start: usize, width: usize, measure: &dyn WidthMeasure) -> String {
+    let period = ansi::display_width(pattern, measure);
+    if pattern == " " || period == 0 {
+        return " ".repeat(width);
+    }
+
+    // the visible characters of the pattern, with the escape sequences
+    // just before them and their position in the pattern
+    let mut parts = Vec::new();
+    let mut escapes = String::new();
+    let mut position = 0;
+    for segment in ansi::segments(pattern) {
+        match segment {
+            ansi::Segment::Text(visible) => {
+                for grapheme in visible.graphemes(true) {
+                    let grapheme_width = measure.grapheme_width(grapheme);
+                    parts.push((
+                        std::mem::take(&mut escapes),
+                        grapheme,
+                        grapheme_width,
+                        position,
+                    ));
+                    position += grapheme.len();
+                }
+            }
+            ansi::Segment::Escape(escape, _) => {
+                escapes.push_str(escape);
+                position += escape.len();
+            }
+        }
+    }
+    // the escape sequences at the end of the pattern
+    let trailing = escapes;
+
+    let mut filled = String::new();
+    let mut started = false;
+    let end = start + width;
+    let mut column = start;
+    while column < end {
+        // the part of the pattern that starts at this column, if any
+        let mut phase = column % period;
+        let mut part = None;
+        for (i, (_, _, grapheme_width, _)) in parts.iter().enumerate() {
+            if phase == 0 && *grapheme_width > 0 {
+                part = Some(i);
+                break;
+            } else if phase < *grapheme_width {
+                // in the middle of a wide character
+                break;
+            }
+            phase -= grapheme_width;
+        }
+
+        match part.map(|i| (i, &parts[i])) {
+            Some((i, (escapes, grapheme, grapheme_width, position)))
+                if column + grapheme_width <= end =>
+            {
+                if started {
+                    filled.push_str(escapes);
                 } else {
-                    for (word, spacing) in words.iter().zip(spaces) {
-                        aligned.push_str(&" ".repeat(spacing));
-                        aligned.push_str(word);
-                    }
+                    // the pattern may start in the middle of a style
+                    filled.push_str(&ansi::active_style(&pattern[..*position], ""));
+                    started = true;
+                }
+                filled.push_str(grapheme);
+                if i == parts.len() - 1 {
+                    filled.push_str(&trailing);
                 }
+                column += grapheme_width;
+            }
+            _ => {
+                filled.push(' ');
+                column += 1;
+            }
+        }
+    }
 
-                aligned
+    return ansi::close_style(&filled, "");
+}
+
+// the width of a line before its anchor and from its anchor,
+// a line without anchor is placed as if it was just after it
+pub fn anchor_extent(line: &str, anchor: char, measure: &dyn WidthMeasure) -> (usize, usize) {
+    match ansi::find_visible(line, anchor) {
+        Some(position) => (
+            ansi::display_width(&line[..position], measure),
+            ansi::display_width(&line[position..], measure),
+        ),
+        None => (ansi::display_width(line, measure), 0),
+    }
+}
+
+// pads a line on the left so that its anchor comes after `before` columns
+pub fn anchor_line(line: &str, anchor: char, before: usize, measure: &dyn WidthMeasure) -> String {
+    let (line_before, _) = anchor_extent(line, anchor, measure);
+    return " ".repeat(before.saturating_sub(line_before)) + line;
+}
+
+// divides `spaces` between the words (at least one in each gap)
+// according to the justification strategy
+fn spread_spaces(
+    words: &[&str],
+    spaces: usize,
+    index: usize,
+    justification: Justification,
+) -> Vec<usize> {
+    let gaps = words.len().saturating_sub(1);
+    if gaps == 0 {
+        return Vec::new();
+    }
+    let extra = spaces % gaps;
+
+    // the gaps that get one more space than the others
+    let widened: Vec<usize> = match justification {
+        Justification::EVEN => return split_evenly(spaces, gaps),
+
+        Justification::PUNCTUATION => {
+            // the end of sentences first, then the other punctuation marks
+            let rank = |gap: &usize| match ansi::last_char(words[*gap]) {
+                Some('.' | '!' | '?') => 0,
+                Some(',' | ';' | ':') => 1,
+                _ => 2,
+            };
+            let mut punctuated: Vec<usize> = (0..gaps).filter(|gap| rank(gap) < 2).collect();
+            punctuated.sort_by_key(rank);
+            punctuated.truncate(extra);
+
+            // what's left is spread evenly between the other gaps
+            let others: Vec<usize> = (0..gaps).filter(|gap| rank(gap) == 2).collect();
+            let leftover = split_evenly(extra - punctuated.len(), others.len());
+            punctuated.extend(
+                others
+                    .iter()
+                    .zip(leftover)
+                    .filter(|(_, spaces)| *spaces > 0)
+                    .map(|(gap, _)| *gap),
+            );
+            punctuated
+        }
+
+        Justification::ALTERNATING => {
+            if index % 2 == 0 {
+                (0..extra).collect()
+            } else {
+                (gaps - extra..gaps).collect()
+            }
+        }
+
+        Justification::RANDOM(seed) => {
+            // a partial shuffle of the gaps, different for each line
+            let mut state = seed ^ (index as u64);
+            let mut shuffled: Vec<usize> = (0..gaps).collect();
+            for i in 0..extra {
+                let j = i + (pseudo_random(&mut state) % (gaps - i) as u64) as usize;
+                shuffled.swap(i, j);
             }
+            shuffled.truncate(extra);
+            shuffled
         }
+    };
+
+    let mut spread = vec![spaces / gaps; gaps];
+    for gap in widened {
+        spread[gap] += 1;
     }
+
+    return spread;
+}
+
+// makes a line that is too wide fit, according to the overflow policy
+fn overflow_line(
+    line: &str,
+    width: usize,
+    line_width: usize,
+    settings: LineSettings,
+) -> Result<String, Error> {
+    let measure = settings.measure;
+    let fitted = match settings.overflow {
+        Overflow::SPILL => return Ok(ansi::close_style(line, "")),
+
+        Overflow::TRUNCATE => ansi::truncate(line, width, measure),
+
+        Overflow::ELLIPSIS => {
+            // the ellipsis is wide with some measures
+            match width.checked_sub(measure.width("…")) {
+                Some(available) => ansi::truncate(line, available, measure) + "…",
+                None => String::new(),
+            }
+        }
+
+        Overflow::ERROR => {
+            return Err(Error::LineTooWide {
+                width,
+                actual: line_width,
+            })
+        }
+    };
+
+    // a wide character may have been cut, leaving some space
+    let remaining = width - ansi::display_width(&fitted, measure);
+    return Ok(ansi::close_style(&fitted, "") + &" ".repeat(remaining));
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::Tabs;
 
     #[test]
     fn test_align_line() {
         // left alignment
         assert_eq!(
-            align_line("even", 10, Alignment::LEFT, false),
+            align_line(
+                "even",
+                10,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("even      ")
         );
 
         // right alignment
         assert_eq!(
-            align_line("even", 10, Alignment::RIGHT, false),
+            align_line(
+                "even",
+                10,
+                Alignment::RIGHT,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("      even")
         );
 
         // center with an even number of characters left
         assert_eq!(
-            align_line("even", 10, Alignment::CENTER, false),
+            align_line(
+                "even",
+                10,
+                Alignment::CENTER,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("   even   ")
         );
         // center with an odd number of characters left
         assert_eq!(
-            align_line("odd", 10, Alignment::CENTER, false),
+            align_line(
+                "odd",
+                10,
+                Alignment::CENTER,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("   odd    ")
         );
 
         // justified
         assert_eq!(
-            align_line("even odd odd even", 19, Alignment::JUSTIFY, false),
+            align_line(
+                "even odd odd even",
+                19,
+                Alignment::JUSTIFY,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("even  odd odd  even")
         );
         // last line justified
         assert_eq!(
-            align_line("even odd odd even", 19, Alignment::JUSTIFY, true),
+            align_line(
+                "even odd odd even",
+                19,
+                Alignment::JUSTIFY,
+                true,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("even odd odd even  ")
         );
         // one word justified
         assert_eq!(
-            align_line("even", 19, Alignment::JUSTIFY, false),
+            align_line(
+                "even",
+                19,
+                Alignment::JUSTIFY,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
             String::from("even               ")
         );
         // empty line justified
         assert_eq!(
-            align_line("", 19, Alignment::JUSTIFY, false),
+            align_line("", 19, Alignment::JUSTIFY, false, 0, Overflow::SPILL.into()).unwrap(),
             String::from("                   ")
         );
 
         // empty lines
         assert_eq!(
-            align_line("", 10, Alignment::LEFT, false),
+            align_line("", 10, Alignment::LEFT, false, 0, Overflow::SPILL.into()).unwrap(),
             String::from("          ")
         );
         assert_eq!(
-            align_line("", 10, Alignment::RIGHT, false),
+            align_line("", 10, Alignment::RIGHT, false, 0, Overflow::SPILL.into()).unwrap(),
             String::from("          ")
         );
         assert_eq!(
-            align_line("", 10, Alignment::CENTER, false),
+            align_line("", 10, Alignment::CENTER, false, 0, Overflow::SPILL.into()).unwrap(),
             String::from("          ")
         );
         assert_eq!(
-            align_line("", 10, Alignment::JUSTIFY, false),
+            align_line("", 10, Alignment::JUSTIFY, false, 0, Overflow::SPILL.into()).unwrap(),
             String::from("          ")
         );
         assert_eq!(
-            align_line("", 10, Alignment::JUSTIFY, true),
+            align_line("", 10, Alignment::JUSTIFY, true, 0, Overflow::SPILL.into()).unwrap(),
             String::from("          ")
         );
     }
 
+    #[test]
+    fn test_align_line_justification() {
+        let justify = |line, width, index, justification| {
+            let settings = LineSettings {
+                justification,
+                ..Default::default()
+            };
+            align_line(line, width, Alignment::JUSTIFY, false, index, settings).unwrap()
+        };
+
+        assert_eq!(
+            justify("a b c d e", 12, 0, Justification::EVEN),
+            "a  b  c d  e"
+        );
+
+        // extra spaces after punctuation first
+        assert_eq!(
+            justify(
+                "one. two three, four five",
+                27,
+                0,
+                Justification::PUNCTUATION
+            ),
+            "one.  two three,  four five"
+        );
+        assert_eq!(
+            justify(
+                "one. two three, four five",
+                28,
+                0,
+                Justification::PUNCTUATION
+            ),
+            "one.  two  three,  four five"
+        );
+        assert_eq!(
+            justify(
+                "\x1b[1mone.\x1b[0m two three",
+                15,
+                0,
+                Justification::PUNCTUATION
+            ),
+            "\x1b[1mone.\x1b[0m  two three"
+        );
+
+        // left, then right
+        assert_eq!(
+            justify("a b c d e", 12, 0, Justification::ALTERNATING),
+            "a  b  c  d e"
+        );
+        assert_eq!(
+            justify("a b c d e", 12, 1, Justification::ALTERNATING),
+            "a b  c  d  e"
+        );
+
+        // the same seed and line give the same output
+        let random = justify("a b c d e f g h", 20, 3, Justification::RANDOM(42));
+        assert_eq!(
+            random,
+            justify("a b c d e f g h", 20, 3, Justification::RANDOM(42))
+        );
+        assert_eq!(random.len(), 20);
+        assert_eq!(random.split_whitespace().collect::<String>(), "abcdefgh");
+    }
+
+    #[test]
+    fn test_align_line_last_line() {
+        let last = |last_line| {
+            let settings = LineSettings {
+                last_line,
+                ..Default::default()
+            };
+            align_line("even odd", 10, Alignment::JUSTIFY, true, 0, settings).unwrap()
+        };
+
+        assert_eq!(last(LastLine::LEFT), "even odd  ");
+        assert_eq!(last(LastLine::RIGHT), "  even odd");
+        assert_eq!(last(LastLine::CENTER), " even odd ");
+        assert_eq!(last(LastLine::JUSTIFY), "even   odd");
+        // 8 out of 10 is 80%
+        assert_eq!(last(LastLine::FILLED(80)), "even   odd");
+        assert_eq!(last(LastLine::FILLED(90)), "even odd  ");
+
+        // only for justified text
+        let settings = LineSettings {
+            last_line: LastLine::RIGHT,
+            ..Default::default()
+        };
+        assert_eq!(
+            align_line("even odd", 10, Alignment::LEFT, true, 0, settings).unwrap(),
+            "even odd  "
+        );
+    }
+
+    #[test]
+    fn test_align_line_overflow() {
+        let wide = "overflowing";
+
+        assert_eq!(
+            align_line(wide, 8, Alignment::RIGHT, false, 0, Overflow::SPILL.into()),
+            Ok(String::from("overflowing"))
+        );
+        assert_eq!(
+            align_line(
+                wide,
+                8,
+                Alignment::RIGHT,
+                false,
+                0,
+                Overflow::TRUNCATE.into()
+            ),
+            Ok(String::from("overflow"))
+        );
+        assert_eq!(
+            align_line(
+                wide,
+                8,
+                Alignment::RIGHT,
+                false,
+                0,
+                Overflow::ELLIPSIS.into()
+            ),
+            Ok(String::from("overflo…"))
+        );
+        assert_eq!(
+            align_line(wide, 8, Alignment::RIGHT, false, 0, Overflow::ERROR.into()),
+            Err(Error::LineTooWide {
+                width: 8,
+                actual: 11
+            })
+        );
+
+        // wide characters at the edge
+        assert_eq!(
+            align_line(
+                "日本語",
+                5,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::TRUNCATE.into()
+            ),
+            Ok(String::from("日本 "))
+        );
+        assert_eq!(
+            align_line(
+                "日本語",
+                5,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::ELLIPSIS.into()
+            ),
+            Ok(String::from("日本…"))
+        );
+        assert_eq!(
+            align_line(
+                "日本語",
+                4,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::ELLIPSIS.into()
+            ),
+            Ok(String::from("日… "))
+        );
+
+        // styles are still closed
+        assert_eq!(
+            align_line(
+                "\x1b[4moverflowing",
+                4,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::ELLIPSIS.into()
+            ),
+            Ok(String::from("\x1b[4move…\x1b[0m"))
+        );
+
+        // no space at all
+        assert_eq!(
+            align_line(
+                wide,
+                0,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::ELLIPSIS.into()
+            ),
+            Ok(String::new())
+        );
+    }
+
+    #[test]
+    fn test_align_measure() {
+        let options = crate::Options::new(6).measure(&crate::CjkWidth);
+
+        // the ambiguous characters are wide
+        assert_eq!(align("± →", Alignment::RIGHT, options.clone()), " ± →");
+        assert_eq!(align("± →", Alignment::RIGHT, 6), "   ± →");
+        // the lines are wrapped with the measure
+        assert_eq!(
+            align(
+                "±± ±± ±±",
+                Alignment::LEFT,
+                crate::Options::new(5).measure(&crate::CjkWidth)
+            ),
+            "±± \n±± \n±± "
+        );
+        // so is the ellipsis
+        assert_eq!(
+            align(
+                "supercalifragilistic",
+                Alignment::LEFT,
+                options.break_words(false).overflow(Overflow::ELLIPSIS)
+            ),
+            "supe…"
+        );
+    }
+
+    #[test]
+    fn test_align_fill() {
+        let options = crate::Options::new(15).fill("=");
+        assert_eq!(
+            align("Title", Alignment::CENTER, options),
+            "==== Title ===="
+        );
+
+        // leaders
+        let options = crate::Options::new(12).fill(".");
+        assert_eq!(
+            align("Intro\nChapter 1", Alignment::LEFT, options),
+            "Intro ......\nChapter 1 .."
+        );
+        // the pattern lines up from one line to the next
+        let options = crate::Options::new(10).fill(". ");
+        assert_eq!(
+            align("ab\nabc", Alignment::LEFT, options),
+            "ab  . . . \nabc . . . "
+        );
+        // wide patterns
+        let options = crate::Options::new(7).fill("・");
+        assert_eq!(align("ab", Alignment::RIGHT, options), "・・ ab");
+        assert_eq!(fill_pattern("・", 3, 4, &crate::UnicodeWidth), " ・ ");
+        // styled patterns
+        let options = crate::Options::new(8).fill("\x1b[2m.\x1b[0m");
+        assert_eq!(
+            align("ab", Alignment::LEFT, options),
+            format!("ab {}", "\x1b[2m.\x1b[0m".repeat(5))
+        );
+        assert_eq!(
+            fill_pattern("\x1b[31m-\x1b[1m=", 1, 3, &crate::UnicodeWidth),
+            "\x1b[31m\x1b[1m=\x1b[31m-\x1b[1m=\x1b[0m"
+        );
+        // empty lines aren't filled
+        let options = crate::Options::new(3).fill("-");
+        assert_eq!(align("a\n\nb", Alignment::LEFT, options), "a -\n   \nb -");
+    }
+
+    #[test]
+    fn test_align_overflow() {
+        let text = "a supercalifragilistic word";
+        let options = crate::Options::new(10).break_words(false);
+
+        assert_eq!(
+            align(text, Alignment::CENTER, options.clone()),
+            String::from("    a     \nsupercalifragilistic\n   word   ")
+        );
+        assert_eq!(
+            align(
+                text,
+                Alignment::CENTER,
+                options.clone().overflow(Overflow::ELLIPSIS)
+            ),
+            String::from("    a     \nsupercali…\n   word   ")
+        );
+        assert_eq!(
+            try_align(text, Alignment::CENTER, options.overflow(Overflow::ERROR)),
+            Err(Error::LineTooWide {
+                width: 10,
+                actual: 20
+            })
+        );
+    }
+
     #[test]
     fn test_align() {
         // the doctest
@@ -203,4 +948,182 @@ mod tests {
 
         assert_eq!(align(text, Alignment::CENTER, 20), expected);
     }
+
+    #[test]
+    fn test_align_paragraphs() {
+        let text = "one two three four\nfive six seven\nend";
+
+        // the last line of each paragraph isn't justified
+        let expected =
+            String::from("one      two\nthree four  \nfive     six\nseven       \nend         ");
+
+        assert_eq!(align(text, Alignment::JUSTIFY, 12), expected);
+    }
+
+    #[test]
+    fn test_align_line_graphemes() {
+        let thumbs = "👍🏽";
+
+        assert_eq!(
+            align_line(
+                thumbs,
+                4,
+                Alignment::RIGHT,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
+            format!("  {}", thumbs)
+        );
+        // the clusters are never split
+        assert_eq!(
+            align_line(
+                &thumbs.repeat(3),
+                5,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::ELLIPSIS.into()
+            )
+            .unwrap(),
+            thumbs.repeat(2) + "…"
+        );
+    }
+
+    #[test]
+    fn test_align_graphemes() {
+        let family = "👨\u{200d}👩\u{200d}👧";
+
+        // the clusters are measured as a whole
+        assert_eq!(
+            align(&family.repeat(2), Alignment::LEFT, 4),
+            family.repeat(2)
+        );
+        // long words are broken between the clusters
+        assert_eq!(
+            align(&family.repeat(2), Alignment::LEFT, 3),
+            format!("{} \n{} ", family, family)
+        );
+    }
+
+    #[test]
+    fn test_align_direction() {
+        let rtl = LineSettings {
+            direction: Direction::RTL,
+            ..Default::default()
+        };
+        let ltr = LineSettings::default();
+
+        assert_eq!(
+            align_line("abc", 5, Alignment::START, false, 0, ltr).unwrap(),
+            "abc  "
+        );
+        assert_eq!(
+            align_line("abc", 5, Alignment::END, false, 0, ltr).unwrap(),
+            "  abc"
+        );
+        assert_eq!(
+            align_line("abc", 5, Alignment::START, false, 0, rtl).unwrap(),
+            "  abc"
+        );
+        assert_eq!(
+            align_line("abc", 5, Alignment::END, false, 0, rtl).unwrap(),
+            "abc  "
+        );
+        // absolute alignments don't change
+        assert_eq!(
+            align_line("abc", 5, Alignment::LEFT, false, 0, rtl).unwrap(),
+            "abc  "
+        );
+
+        // the last line of justified text goes to the start
+        let options = crate::Options::new(7).direction(Direction::RTL);
+        assert_eq!(
+            align("a b c d e f", Alignment::JUSTIFY, options),
+            "a b c d\n    e f"
+        );
+    }
+
+    #[test]
+    fn test_align_tabs() {
+        // tab stops every 8 columns by default
+        assert_eq!(align("a\tb", Alignment::RIGHT, 10), " a       b");
+
+        let options = crate::Options::new(10);
+        assert_eq!(
+            align(
+                "ab\tc\n\td",
+                Alignment::LEFT,
+                options.clone().tabs(Tabs::EXPAND(4))
+            ),
+            "ab  c     \n    d     "
+        );
+        assert_eq!(
+            align(
+                "a\tb",
+                Alignment::LEFT,
+                options.clone().tabs(Tabs::REPLACE(' '))
+            ),
+            "a b       "
+        );
+        assert_eq!(
+            try_align("a\tb", Alignment::LEFT, options.tabs(Tabs::ERROR)),
+            Err(Error::TabCharacter { position: 1 })
+        );
+    }
+
+    #[test]
+    fn test_align_line_ansi() {
+        let red = "\x1b[31merror\x1b[0m";
+
+        assert_eq!(
+            align_line(red, 10, Alignment::LEFT, false, 0, Overflow::SPILL.into()).unwrap(),
+            String::from("\x1b[31merror\x1b[0m     ")
+        );
+        assert_eq!(
+            align_line(red, 10, Alignment::RIGHT, false, 0, Overflow::SPILL.into()).unwrap(),
+            String::from("     \x1b[31merror\x1b[0m")
+        );
+        assert_eq!(
+            align_line(red, 10, Alignment::CENTER, false, 0, Overflow::SPILL.into()).unwrap(),
+            String::from("  \x1b[31merror\x1b[0m   ")
+        );
+        assert_eq!(
+            align_line(
+                "an \x1b[31merror\x1b[0m here",
+                16,
+                Alignment::JUSTIFY,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
+            String::from("an   \x1b[31merror\x1b[0m  here")
+        );
+
+        // unterminated styles are closed before the padding
+        assert_eq!(
+            align_line(
+                "\x1b[1mbold",
+                6,
+                Alignment::LEFT,
+                false,
+                0,
+                Overflow::SPILL.into()
+            )
+            .unwrap(),
+            String::from("\x1b[1mbold\x1b[0m  ")
+        );
+    }
+
+    #[test]
+    fn test_align_ansi() {
+        let text = "\x1b[32mgreen text\x1b[0m then plain";
+
+        let expected =
+            String::from("    \x1b[32mgreen\x1b[0m\n\x1b[32mtext\x1b[0m then\n    plain");
+
+        assert_eq!(align(text, Alignment::RIGHT, 9), expected);
+    }
 }
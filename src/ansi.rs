@@ -0,0 +1,463 @@
+// handling of ANSI escape sequences
+
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::WidthMeasure;
+#[cfg(test)]
+use crate::{CjkWidth, UnicodeWidth};
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+
+// resets every style
+pub const RESET: &str = "\x1b[0m";
+
+// ends a hyperlink
+const END_LINK: &str = "\x1b]8;;\x1b\\";
+
+// the different kinds of escape sequences we know about
+#[derive(Debug, PartialEq)]
+pub enum Escape {
+    // Select Graphic Rendition (colors, bold, underline ...)
+    SGR,
+    // any other Control Sequence
+    CSI,
+    // Operating System Command (window title, hyperlinks ...)
+    OSC,
+    // a lone ESC followed by a single character
+    OTHER,
+}
+
+// if `text` starts with an escape sequence, returns its kind and its length in bytes
+fn escape_at(text: &str) -> Option<(Escape, usize)> {
+    let mut chars = text.char_indices();
+
+    if chars.next()?.1 != ESC {
+        return None;
+    }
+
+    match chars.next() {
+        // a trailing ESC is still an escape sequence
+        None => Some((Escape::OTHER, ESC.len_utf8())),
+
+        Some((_, '[')) => {
+            // parameters and intermediate bytes until a final byte in 0x40–0x7E
+            for (i, ch) in chars {
+                if ('\x40'..='\x7e').contains(&ch) {
+                    let kind = if ch == 'm' { Escape::SGR } else { Escape::CSI };
+                    return Some((kind, i + ch.len_utf8()));
+                }
+            }
+            Some((Escape::CSI, text.len()))
+        }
+
+        Some((_, ']')) => {
+            // terminated by BEL or by ST (ESC \)
+            let mut previous = ' ';
+            for (i, ch) in chars {
+                if ch == BEL || (previous == ESC && ch == '\\') {
+                    return Some((Escape::OSC, i + ch.len_utf8()));
+                }
+                previous = ch;
+            }
+            Some((Escape::OSC, text.len()))
+        }
+
+        Some((i, ch)) => Some((Escape::OTHER, i + ch.len_utf8())),
+    }
+}
+
+// a part of a string, either visible text or an escape sequence
+pub enum Segment<'a> {
+    Text(&'a str),
+    Escape(&'a str, Escape),
+}
+
+// splits a string into visible text and escape sequences
+pub fn segments(text: &str) -> Vec<Segment<'_>> {
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < text.len() {
+        match escape_at(&text[i..]) {
+            Some((kind, len)) => {
+                if start < i {
+                    segments.push(Segment::Text(&text[start..i]));
+                }
+                segments.push(Segment::Escape(&text[i..i + len], kind));
+                i += len;
+                start = i;
+            }
+            None => i += text[i..].chars().next().map_or(1, char::len_utf8),
+        }
+    }
+
+    if start < text.len() {
+        segments.push(Segment::Text(&text[start..]));
+    }
+
+    return segments;
+}
+
+// the number of columns a string takes when displayed,
+// ignoring escape sequences
+pub fn display_width(text: &str, measure: &dyn WidthMeasure) -> usize {
+    let mut width = 0;
+    for segment in segments(text) {
+        if let Segment::Text(visible) = segment {
+            width += measure.width(visible);
+        }
+    }
+    return width;
+}
+
+#[test]
+fn test_display_width() {
+    assert_eq!(display_width("error", &UnicodeWidth), 5);
+    assert_eq!(display_width("\x1b[31merror\x1b[0m", &UnicodeWidth), 5);
+    assert_eq!(
+        display_width("\x1b[1;38;5;208mwarning\x1b[m!", &UnicodeWidth),
+        8
+    );
+    // cursor movement
+    assert_eq!(display_width("a\x1b[2Kb", &UnicodeWidth), 2);
+    // hyperlinks, terminated by ST or BEL
+    assert_eq!(
+        display_width(
+            "\x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07",
+            &UnicodeWidth
+        ),
+        4
+    );
+    // unterminated sequences
+    assert_eq!(display_width("abc\x1b[31", &UnicodeWidth), 3);
+    assert_eq!(display_width("abc\x1b", &UnicodeWidth), 3);
+    // wide characters
+    assert_eq!(display_width("\x1b[32m你好\x1b[0m", &UnicodeWidth), 4);
+    // grapheme clusters
+    assert_eq!(display_width("e\u{301}", &UnicodeWidth), 1);
+    assert_eq!(display_width("👍🏽", &UnicodeWidth), 2);
+    assert_eq!(display_width("👨\u{200d}👩\u{200d}👧", &UnicodeWidth), 2);
+    assert_eq!(display_width("🇫🇷", &UnicodeWidth), 2);
+    assert_eq!(display_width("❤\u{fe0f}", &UnicodeWidth), 2);
+    // other measures
+    assert_eq!(display_width("\x1b[1m±1\x1b[0m", &CjkWidth), 3);
+}
+
+// the last visible character of a string
+pub fn last_char(text: &str) -> Option<char> {
+    return segments(text)
+        .iter()
+        .rev()
+        .find_map(|segment| match segment {
+            Segment::Text(visible) => visible.chars().last(),
+            Segment::Escape(..) => None,
+        });
+}
+
+#[test]
+fn test_last_char() {
+    assert_eq!(last_char("end."), Some('.'));
+    assert_eq!(last_char("\x1b[1mbold,\x1b[0m"), Some(','));
+    assert_eq!(last_char("\x1b[0m"), None);
+    assert_eq!(last_char(""), None);
+}
+
+// replaces the tabs with spaces up to the next tab stop,
+// the stops being every `stop` columns from the start of each line
+pub fn expand_tabs(text: &str, stop: usize, measure: &dyn WidthMeasure) -> String {
+    let mut expanded = String::new();
+    let mut column = 0;
+
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(visible) => {
+                for grapheme in visible.graphemes(true) {
+                    match grapheme {
+                        "\t" => {
+                            let spaces = if stop == 0 { 0 } else { stop - column % stop };
+                            expanded.push_str(&" ".repeat(spaces));
+                            column += spaces;
+                        }
+                        "\n" | "\r\n" => {
+                            expanded.push_str(grapheme);
+                            column = 0;
+                        }
+                        _ => {
+                            expanded.push_str(grapheme);
+                            column += measure.grapheme_width(grapheme);
+                        }
+                    }
+                }
+            }
+            Segment::Escape(escape, _) => expanded.push_str(escape),
+        }
+    }
+
+    return expanded;
+}
+
+#[test]
+fn test_expand_tabs() {
+    assert_eq!(expand_tabs("a\tbc\td", 4, &UnicodeWidth), "a   bc  d");
+    assert_eq!(
+        expand_tabs("abcd\te\n\tf", 4, &UnicodeWidth),
+        "abcd    e\n    f"
+    );
+    // escape sequences don't move the cursor
+    assert_eq!(
+        expand_tabs("\x1b[1ma\x1b[0m\tb", 4, &UnicodeWidth),
+        "\x1b[1ma\x1b[0m   b"
+    );
+    assert_eq!(expand_tabs("日\tb", 4, &UnicodeWidth), "日  b");
+    assert_eq!(expand_tabs("a\tb", 0, &UnicodeWidth), "ab");
+}
+
+// the byte position of the first visible occurrence of `ch`
+pub fn find_visible(text: &str, ch: char) -> Option<usize> {
+    let mut position = 0;
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(visible) => {
+                if let Some(found) = visible.find(ch) {
+                    return Some(position + found);
+                }
+                position += visible.len();
+            }
+            Segment::Escape(escape, _) => position += escape.len(),
+        }
+    }
+    return None;
+}
+
+#[test]
+fn test_find_visible() {
+    assert_eq!(find_visible("3.14", '.'), Some(1));
+    // not inside escape sequences
+    assert_eq!(find_visible("\x1b[1;31mkey;value", ';'), Some(10));
+    assert_eq!(find_visible("42", '.'), None);
+}
+
+// the target of an OSC 8 hyperlink sequence, empty if it ends a link
+fn hyperlink_target(escape: &str) -> Option<&str> {
+    let params = escape.strip_prefix("\x1b]8;")?;
+    let params = params
+        .strip_suffix(BEL)
+        .or_else(|| params.strip_suffix("\x1b\\"))
+        .unwrap_or(params);
+    return params.split_once(';').map(|(_, target)| target);
+}
+
+// the SGR sequences and the hyperlink still in effect at the end
+// of `text`, starting with the ones in effect before it
+pub fn active_style(text: &str, before: &str) -> String {
+    let mut style = String::new();
+    let mut link = String::new();
+    for segment in segments(&(String::from(before) + text)) {
+        match segment {
+            Segment::Escape(escape, Escape::SGR) => {
+                if escape == "\x1b[m" || escape == RESET {
+                    style.clear();
+                } else {
+                    style.push_str(escape);
+                }
+            }
+            Segment::Escape(escape, Escape::OSC) => match hyperlink_target(escape) {
+                Some("") => link.clear(),
+                Some(_) => link = String::from(escape),
+                None => (),
+            },
+            _ => (),
+        }
+    }
+    return style + &link;
+}
+
+#[test]
+fn test_active_style() {
+    assert_eq!(active_style("plain", ""), "");
+    assert_eq!(active_style("\x1b[31merror\x1b[0m", ""), "");
+    assert_eq!(active_style("\x1b[31merror", ""), "\x1b[31m");
+    assert_eq!(active_style("\x1b[31m\x1b[1merror", ""), "\x1b[31m\x1b[1m");
+    assert_eq!(active_style("still red", "\x1b[31m"), "\x1b[31m");
+    assert_eq!(active_style("back\x1b[m to normal", "\x1b[31m"), "");
+    // other sequences don't change the style
+    assert_eq!(
+        active_style("\x1b[2K\x1b]0;title\x07", "\x1b[4m"),
+        "\x1b[4m"
+    );
+    // hyperlinks
+    assert_eq!(
+        active_style("\x1b]8;;https://example.com\x1b\\link", "\x1b[1m"),
+        "\x1b[1m\x1b]8;;https://example.com\x1b\\"
+    );
+    assert_eq!(
+        active_style("link\x1b]8;;\x07", "\x1b]8;id=1;https://example.com\x07"),
+        ""
+    );
+}
+
+// reapplies the style carried over from the previous line
+pub fn reopen_style(line: &str, style: &str) -> String {
+    if line.is_empty() {
+        // no need to style an empty line
+        String::new()
+    } else {
+        String::from(style) + line
+    }
+}
+
+#[test]
+fn test_reopen_style() {
+    assert_eq!(reopen_style("text", ""), "text");
+    assert_eq!(reopen_style("text", "\x1b[31m"), "\x1b[31mtext");
+    assert_eq!(reopen_style("", "\x1b[31m"), "");
+}
+
+// makes sure a line doesn't leak its style or its hyperlink,
+// by resetting them at the end if needed
+pub fn close_style(line: &str, before: &str) -> String {
+    let mut closed = String::from(line);
+    let active = active_style(line, before);
+    if segments(&active)
+        .iter()
+        .any(|segment| matches!(segment, Segment::Escape(_, Escape::SGR)))
+    {
+        closed.push_str(RESET);
+    }
+    if segments(&active)
+        .iter()
+        .any(|segment| matches!(segment, Segment::Escape(_, Escape::OSC)))
+    {
+        closed.push_str(END_LINK);
+    }
+    return closed;
+}
+
+#[test]
+fn test_close_style() {
+    assert_eq!(close_style("plain", ""), "plain");
+    assert_eq!(
+        close_style("\x1b[31merror\x1b[0m", ""),
+        "\x1b[31merror\x1b[0m"
+    );
+    assert_eq!(close_style("\x1b[31merror", ""), "\x1b[31merror\x1b[0m");
+    assert_eq!(close_style("still red", "\x1b[31m"), "still red\x1b[0m");
+    assert_eq!(
+        close_style("\x1b[4m\x1b]8;;https://example.com\x1b\\link", ""),
+        "\x1b[4m\x1b]8;;https://example.com\x1b\\link\x1b[0m\x1b]8;;\x1b\\"
+    );
+}
+
+// cuts the end of `text` so that it is at most `width` columns wide,
+// keeping all the escape sequences
+pub fn truncate(text: &str, width: usize, measure: &dyn WidthMeasure) -> String {
+    let mut truncated = String::new();
+    let mut used = 0;
+    let mut full = false;
+
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(visible) => {
+                // never split a cluster
+                for grapheme in visible.graphemes(true) {
+                    let grapheme_width = measure.grapheme_width(grapheme);
+                    if !full && used + grapheme_width <= width {
+                        used += grapheme_width;
+                        truncated.push_str(grapheme);
+                    } else {
+                        // the following characters are dropped too,
+                        // even if they would fit
+                        full = true;
+                    }
+                }
+            }
+            Segment::Escape(escape, _) => truncated.push_str(escape),
+        }
+    }
+
+    return truncated;
+}
+
+#[test]
+fn test_truncate() {
+    assert_eq!(truncate("overflowing", 4, &UnicodeWidth), "over");
+    assert_eq!(truncate("short", 10, &UnicodeWidth), "short");
+    assert_eq!(truncate("anything", 0, &UnicodeWidth), "");
+    // escapes are kept so that the styles are closed properly
+    assert_eq!(
+        truncate("\x1b[31mover\x1b[1mflowing\x1b[0m", 6, &UnicodeWidth),
+        "\x1b[31mover\x1b[1mfl\x1b[0m"
+    );
+    // wide characters that don't fit are removed entirely
+    assert_eq!(truncate("你好", 3, &UnicodeWidth), "你");
+    assert_eq!(truncate("你a", 1, &UnicodeWidth), "");
+    // so are grapheme clusters
+    assert_eq!(truncate("a👨\u{200d}👩\u{200d}👧b", 2, &UnicodeWidth), "a");
+    assert_eq!(
+        truncate("a👨\u{200d}👩\u{200d}👧b", 3, &UnicodeWidth),
+        "a👨\u{200d}👩\u{200d}👧"
+    );
+    assert_eq!(truncate("ae\u{301}b", 2, &UnicodeWidth), "ae\u{301}");
+}
+
+// breaks `text` into parts at most `width` columns wide (or a single
+// grapheme cluster if it is wider), keeping the escape sequences
+pub fn break_apart<'t>(text: &'t str, width: usize, measure: &dyn WidthMeasure) -> Vec<&'t str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut end = 0;
+    let mut used = 0;
+    let mut position = 0;
+
+    for segment in segments(text) {
+        match segment {
+            Segment::Text(visible) => {
+                // never split a cluster
+                for grapheme in visible.graphemes(true) {
+                    let grapheme_width = measure.grapheme_width(grapheme);
+                    if used > 0 && used + grapheme_width > width {
+                        parts.push(&text[start..end]);
+                        start = end;
+                        used = 0;
+                    }
+                    used += grapheme_width;
+                    position += grapheme.len();
+                    end = position;
+                }
+            }
+            Segment::Escape(escape, _) => position += escape.len(),
+        }
+    }
+
+    parts.push(&text[start..]);
+    return parts;
+}
+
+#[test]
+fn test_break_apart() {
+    assert_eq!(
+        break_apart("overflowing", 4, &UnicodeWidth),
+        ["over", "flow", "ing"]
+    );
+    assert_eq!(break_apart("short", 10, &UnicodeWidth), ["short"]);
+    assert_eq!(
+        break_apart("\x1b[31mover\x1b[1mflow\x1b[0m", 4, &UnicodeWidth),
+        ["\x1b[31mover", "\x1b[1mflow\x1b[0m"]
+    );
+    // clusters aren't split, even if they are too wide
+    assert_eq!(
+        break_apart(
+            "👨\u{200d}👩\u{200d}👧👨\u{200d}👩\u{200d}👧",
+            3,
+            &UnicodeWidth
+        ),
+        ["👨\u{200d}👩\u{200d}👧", "👨\u{200d}👩\u{200d}👧"]
+    );
+    assert_eq!(break_apart("你好", 1, &UnicodeWidth), ["你", "好"]);
+    assert_eq!(
+        break_apart("ae\u{301}b", 2, &UnicodeWidth),
+        ["ae\u{301}", "b"]
+    );
+}
@@ -1,6 +1,17 @@
+use std::borrow::Cow;
+
+use crate::align::anchor_extent;
+use crate::ansi;
 use crate::utils::*;
 
+use crate::layout::Column;
+use crate::Alignment;
+use crate::Direction;
+use crate::Error;
+use crate::Gutters;
 use crate::Layout;
+use crate::Lines;
+#[cfg(test)]
 use crate::Spacing;
 
 /// Wraps and aligns text within columns.
@@ -9,16 +20,35 @@ use crate::Spacing;
 /// The number of columns is the number of strings,
 /// not based on the layout.
 ///
-/// `width_or_options` can either be an integer or [textwrap::Options],
-/// see the documentation of `textwrap` for more information.
+/// `width_or_options` can either be an integer, [textwrap::Options]
+/// or [Options](crate::Options), see the documentation of `textwrap`
+/// for more information.
 /// (Note that the options `break_word` and `word_splitter` are overriden
 /// to `true` and `textwrap::word_splitters::HyphenSplitter` respectively.)
 ///
+/// Like with [align()](crate::align()), ANSI escape sequences are ignored
+/// when measuring the text, and styles never spill over the padding
+/// or into the next column.
+///
+/// Justified columns leave the last line of each paragraph unjustified,
+/// as well as the last line of each column.
+///
+/// Each column is aligned vertically according to the layout
+/// (see [Layout::vertical_align()](crate::Layout::vertical_align())):
+/// the shorter columns are padded with blank lines at the bottom,
+/// at the top or on both sides.
+///
 /// There are three spacing modes :
 /// * `BETWEEN` add margins bewteen the columns
 /// * `AROUND` add margins between and around the columns
 /// * `NONE` doesn't add any margins
 ///
+/// Custom margins can be used instead with [Gutters](crate::Gutters).
+///
+/// If the direction is right-to-left (see [Options::direction](crate::Options::direction)),
+/// the first string is printed in the rightmost column, the layout being
+/// mirrored as well.
+///
 /// # Examples
 ///
 /// ```
@@ -36,7 +66,7 @@ use crate::Spacing;
 /// }
 /// ```
 /// should display
-/// ```
+/// ```text
 /// I am        I am aligned to the
 /// aligned to   right and take two
 /// the left.     times more space.
@@ -44,6 +74,15 @@ use crate::Spacing;
 ///
 /// [Another example](https://github.com/louisdevie/textflow/blob/main/examples/columns.rs)
 /// is included in the crate.
+///
+/// # Panics
+///
+/// This function panics if the number of strings doesn't match the layout,
+/// if the width is too small to fit the columns, if a line is too wide
+/// and the overflow policy is [Overflow::ERROR](crate::Overflow::ERROR)
+/// or if a string contains a tab and the tab policy is
+/// [Tabs::ERROR](crate::Tabs::ERROR). Use [try_columns()]
+/// to handle these errors instead.
 pub fn columns<
     'a,
     StringsCollection,
@@ -51,9 +90,10 @@ pub fn columns<
     TextwrapWordSep,
     TextwrapWordSplit,
     TextwrapOptions,
+    SpacingOrGutters,
 >(
     content: StringsCollection,
-    spacing: Spacing,
+    spacing_or_gutters: SpacingOrGutters,
     layout: &Layout,
     width_or_options: TextwrapOptions,
 ) -> String
@@ -62,59 +102,284 @@ where
     TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
     TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
     TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
-    TextwrapOptions: Into<textwrap::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    SpacingOrGutters: Into<Gutters>,
+{
+    try_columns(content, spacing_or_gutters, layout, width_or_options).unwrap()
+}
+
+/// Wraps and aligns text within columns, without panicking.
+///
+/// This is the same as [columns()], but returns an [Error] if the number
+/// of strings doesn't match the layout ([Error::TooFewColumns] or
+/// [Error::TooManyColumns]), if the width is smaller than the spacing
+/// ([Error::NotEnoughWidth]), if a column is left without any space
+/// ([Error::ZeroWidthColumn]), if a line doesn't fit in its column
+/// and the overflow policy is [Overflow::ERROR](crate::Overflow::ERROR)
+/// ([Error::LineTooWide]) or if a string contains a tab and the tab policy
+/// is [Tabs::ERROR](crate::Tabs::ERROR) ([Error::TabCharacter]).
+///
+/// # Examples
+///
+/// ```
+/// use textflow::try_columns;
+/// use textflow::Error;
+/// use textflow::Layout;
+/// use textflow::Spacing;
+///
+/// let layout = Layout::from_pattern("<- >--").unwrap();
+///
+/// assert_eq!(
+///     try_columns(["one", "two", "three"], Spacing::BETWEEN, &layout, 31),
+///     Err(Error::TooManyColumns { expected: 2, actual: 3 })
+/// );
+/// ```
+pub fn try_columns<
+    'a,
+    StringsCollection,
+    TextwrapAlgo,
+    TextwrapWordSep,
+    TextwrapWordSplit,
+    TextwrapOptions,
+    SpacingOrGutters,
+>(
+    content: StringsCollection,
+    spacing_or_gutters: SpacingOrGutters,
+    layout: &Layout,
+    width_or_options: TextwrapOptions,
+) -> Result<String, Error>
+where
+    StringsCollection: IntoIterator<Item = &'a str>,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    SpacingOrGutters: Into<Gutters>,
+{
+    let lines: Vec<String> = columns_lines(content, spacing_or_gutters, layout, width_or_options)?
+        .collect::<Result<_, _>>()?;
+
+    // no line feed at the end
+    return Ok(lines.join("\n"));
+}
+
+/// Wraps and aligns text within columns, one line at a time.
+///
+/// This is the same as [try_columns()], but the lines are aligned as they
+/// are requested from the returned [Lines]. Only the wrapped content of
+/// each column is kept in memory, not the output.
+///
+/// # Examples
+///
+/// ```
+/// use textflow::columns_lines;
+/// use textflow::Layout;
+/// use textflow::Spacing;
+///
+/// let layout = Layout::from_pattern("<- >--").unwrap();
+/// let lines = columns_lines(["left", "right"], Spacing::BETWEEN, &layout, 31).unwrap();
+///
+/// let mut stdout = std::io::stdout();
+/// lines.write_io(&mut stdout).unwrap();
+/// ```
+pub fn columns_lines<
+    'a,
+    StringsCollection,
+    TextwrapAlgo,
+    TextwrapWordSep,
+    TextwrapWordSplit,
+    TextwrapOptions,
+    SpacingOrGutters,
+>(
+    content: StringsCollection,
+    spacing_or_gutters: SpacingOrGutters,
+    layout: &Layout,
+    width_or_options: TextwrapOptions,
+) -> Result<Lines<'a>, Error>
+where
+    StringsCollection: IntoIterator<Item = &'a str>,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    SpacingOrGutters: Into<Gutters>,
 {
     let options = width_or_options.into();
+    let gutters = spacing_or_gutters.into();
 
     let vectorised: Vec<&str> = content.into_iter().collect();
 
-    let columns = layout
-        .resolve(
-            options.width - spacing_needed(spacing, vectorised.len()),
-            vectorised.len(),
-        )
-        .unwrap();
+    let needed = spacing_needed(&gutters, vectorised.len(), options.measure);
+    let available = options
+        .wrap
+        .width
+        .checked_sub(needed)
+        .ok_or(Error::NotEnoughWidth {
+            available: options.wrap.width,
+            needed,
+        })?;
+
+    let content_widths: Vec<usize> = vectorised
+        .iter()
+        .zip(layout.alignments(vectorised.len()))
+        .map(|(content, alignment)| preferred_width(content, alignment, &options))
+        .collect();
+    let mut columns = layout.resolve_with(available, &content_widths)?;
+
+    if let Some(index) = columns.iter().position(|column| column.width == 0) {
+        return Err(Error::ZeroWidthColumn { index });
+    }
 
-    let mut wrapped = Vec::new();
-    for (content, column) in vectorised.iter().zip(columns.iter()) {
-        wrapped.push(textwrap::wrap(
-            content,
-            copy_textwrap_options(&options, column.width),
-        ));
+    let mut wrapped = wrap_columns(&vectorised, &columns, &options)?;
+    if options.direction == Direction::RTL {
+        // the first column is on the right
+        columns.reverse();
+        wrapped.reverse();
     }
-    // group lines together
-    crate::utils::invert_2d_vec(&mut wrapped);
 
+    return Ok(Lines::new(
+        wrapped,
+        columns,
+        gutters,
+        options.line_settings(),
+    ));
+}
+
+// puts the cells of each line side by side, separated by the gutters
+pub fn join_grid(grid: &[Vec<String>], gutters: &Gutters) -> String {
+    let lines: Vec<String> = grid.iter().map(|line| join_line(line, gutters)).collect();
+
+    // no line feed at the end
+    return lines.join("\n");
+}
+
+// puts the cells of a line side by side, separated by the gutters
+pub fn join_line(cells: &[String], gutters: &Gutters) -> String {
     let mut formatted = String::new();
 
-    for (i, line) in wrapped.iter().enumerate() {
-        let last_line = i == wrapped.len() - 1;
-        for (i, (content, column)) in line.iter().zip(columns.iter()).enumerate() {
-            match spacing {
-                Spacing::NONE => {}
-                Spacing::BETWEEN => {
-                    if i != 0 {
-                        formatted.push(' ');
-                    }
-                }
-                Spacing::AROUND => {
-                    formatted.push(' ');
+    for (i, cell) in cells.iter().enumerate() {
+        formatted.push_str(gutters.before(i));
+        formatted.push_str(cell);
+    }
+    formatted.push_str(gutters.after());
+
+    return formatted;
+}
+
+// the width a string would take without being wrapped, before and after
+// the anchor if the column is lined up on one (otherwise it's all before)
+pub fn preferred_extent<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    content: &str,
+    alignment: Alignment,
+    options: &crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+) -> (usize, usize) {
+    // if the tabs are rejected, the error comes later
+    let content =
+        replace_tabs(content, options.tabs, options.measure).unwrap_or(Cow::Borrowed(content));
+
+    content
+        .lines()
+        .enumerate()
+        .map(|(i, line)| {
+            let indent = if i == 0 {
+                options.wrap.initial_indent
+            } else {
+                options.wrap.subsequent_indent
+            };
+            let indent = ansi::display_width(indent, options.measure);
+            let line = line.trim_end();
+            match alignment {
+                Alignment::ANCHOR(anchor) => {
+                    let (before, after) = anchor_extent(line, anchor, options.measure);
+                    (indent + before, after)
                 }
+                _ => (indent + ansi::display_width(line, options.measure), 0),
             }
-            formatted.push_str(&crate::align::align_line(
+        })
+        .fold((0, 0), |(before, after), (line_before, line_after)| {
+            (before.max(line_before), after.max(line_after))
+        })
+}
+
+// the width a string would take without being wrapped
+pub fn preferred_width<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    content: &str,
+    alignment: Alignment,
+    options: &crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+) -> usize {
+    let (before, after) = preferred_extent(content, alignment, options);
+    return before + after;
+}
+
+#[test]
+fn test_preferred_width() {
+    let options = crate::Options::new(10);
+    let left = Alignment::LEFT;
+    assert_eq!(preferred_width("one\nthree\ntwo", left, &options), 5);
+    assert_eq!(preferred_width("\x1b[1mbold\x1b[0m", left, &options), 4);
+    assert_eq!(preferred_width("", left, &options), 0);
+
+    // the widest parts before and after the anchor
+    let anchor = Alignment::ANCHOR('.');
+    assert_eq!(preferred_width("1.5\n12345\n2.25", anchor, &options), 8);
+    assert_eq!(
+        preferred_extent("1.5\n12345\n2.25", anchor, &options),
+        (5, 3)
+    );
+
+    let indented = options.initial_indent("* ").subsequent_indent("");
+    assert_eq!(preferred_width("abc\nabcd", left, &indented), 5);
+}
+
+// wraps each string to the width of its column
+pub fn wrap_columns<'a, 't, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    content: &[&'t str],
+    columns: &[Column],
+    options: &crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+) -> Result<Vec<WrappedLines<'t>>, Error>
+where
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+{
+    content
+        .iter()
+        .zip(columns.iter())
+        .map(|(content, column)| {
+            wrap_text(
                 content,
-                column.width,
-                column.alignment,
-                last_line,
-            ));
-        }
-        // no line feed at the end
-        if !last_line {
-            formatted.push('\n');
-        }
+                &copy_textwrap_options(&options.wrap, column.width),
+                options.tabs,
+                options.measure,
+            )
+        })
+        .collect()
+}
+
+// aligns the wrapped content of each column (lining up the anchors
+// with other lines if needed), then returns the lines of the resulting grid
+pub fn align_grid<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    wrapped: Vec<WrappedLines<'_>>,
+    columns: &[Column],
+    anchors: Option<&[(usize, usize)]>,
+    options: &crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+) -> Result<Vec<Vec<String>>, Error> {
+    let mut lines = Lines::new(
+        wrapped,
+        columns.to_vec(),
+        Gutters::new(),
+        options.line_settings(),
+    );
+    if let Some(anchors) = anchors {
+        lines = lines.share_anchors(anchors);
     }
 
-    return formatted;
+    let mut grid = Vec::new();
+    while let Some(cells) = lines.next_cells() {
+        grid.push(cells?);
+    }
+
+    return Ok(grid);
 }
 
 #[test]
@@ -129,8 +394,7 @@ fn test_columns() {
 
     let expected_none = String::from("I am       I am aligned to the\naligned to  right and take two\nthe left.    times more space.");
     let expected_between = String::from("I am        I am aligned to the\naligned to   right and take two\nthe left.     times more space.");
-    // note that `Spacing::AROUND` leaves the end of the line empty (because laziness)
-    let expected_around = String::from(" I am        I am aligned to the\n aligned to   right and take two\n the left.     times more space.");
+    let expected_around = String::from(" I am        I am aligned to the \n aligned to   right and take two \n the left.     times more space. ");
 
     assert_eq!(columns(text, Spacing::NONE, &layout, 30), expected_none);
     assert_eq!(
@@ -139,3 +403,372 @@ fn test_columns() {
     );
     assert_eq!(columns(text, Spacing::AROUND, &layout, 33), expected_around);
 }
+
+#[test]
+fn test_columns_gutters() {
+    let text = ["left", "center", "right"];
+
+    let layout = Layout::from_pattern("< ^ >").unwrap();
+    let gutters = Gutters::new().left("║ ").between(" │ ").right(" ║");
+
+    assert_eq!(
+        columns(text, &gutters, &layout, 28),
+        String::from("║ left   │ center │  right ║")
+    );
+    assert_eq!(
+        columns(text, gutters.clone().left("").right(""), &layout, 22),
+        String::from("left  │ center │ right")
+    );
+}
+
+#[test]
+fn test_columns_ansi() {
+    let text = ["\x1b[31mred and\x1b[0m plain", "\x1b[1mbold"];
+
+    let layout = Layout::from_pattern("> <").unwrap();
+
+    let expected = String::from(
+        "  \x1b[31mred\x1b[0m \x1b[1mbold\x1b[0m \n  \x1b[31mand\x1b[0m      \nplain      ",
+    );
+
+    assert_eq!(columns(text, Spacing::BETWEEN, &layout, 11), expected);
+
+    // hyperlinks are closed and reopened like the styles
+    let text = ["\x1b]8;;u\x1b\\link text here\x1b]8;;\x1b\\", "b"];
+    let layout = Layout::from_pattern("9 -").unwrap();
+    let expected = String::from(
+        "\x1b]8;;u\x1b\\link text\x1b]8;;\x1b\\ b \n\
+         \x1b]8;;u\x1b\\here\x1b]8;;\x1b\\        ",
+    );
+    assert_eq!(columns(text, Spacing::BETWEEN, &layout, 12), expected);
+}
+
+#[test]
+fn test_try_columns() {
+    let layout = Layout::from_pattern("<- >--").unwrap();
+
+    // wrong number of columns
+    assert_eq!(
+        try_columns(["one"], Spacing::BETWEEN, &layout, 30),
+        Err(Error::TooFewColumns {
+            expected: 2,
+            actual: 1
+        })
+    );
+    assert_eq!(
+        try_columns(["one", "two", "three"], Spacing::BETWEEN, &layout, 30),
+        Err(Error::TooManyColumns {
+            expected: 2,
+            actual: 3
+        })
+    );
+
+    // no room for the spacing
+    assert_eq!(
+        try_columns(["one", "two"], Spacing::AROUND, &layout, 2),
+        Err(Error::NotEnoughWidth {
+            available: 2,
+            needed: 3
+        })
+    );
+
+    // no room for the content
+    let fixed = Layout::from_pattern("0 -").unwrap();
+    assert_eq!(
+        try_columns(["one", "two"], Spacing::NONE, &fixed, 10),
+        Err(Error::ZeroWidthColumn { index: 0 })
+    );
+
+    // no columns at all
+    assert_eq!(
+        try_columns([], Spacing::BETWEEN, &Layout::default(), 10),
+        Ok(String::new())
+    );
+
+    // valid
+    assert_eq!(
+        try_columns(["one", "two"], Spacing::BETWEEN, &layout, 10),
+        Ok(String::from("one    two"))
+    );
+}
+
+#[test]
+fn test_columns_overflow() {
+    let text = ["quote", "ok"];
+
+    let layout = Layout::from_pattern("6 >4").unwrap();
+    // the indentation is wider than the second column
+    let options = crate::Options::new(11).initial_indent(">>>>> ");
+
+    // the second column sticks out
+    assert_eq!(
+        columns(text, Spacing::BETWEEN, &layout, options.clone()),
+        String::from(">>>>>  >>>>> \nquote    ok")
+    );
+    assert_eq!(
+        columns(
+            text,
+            Spacing::BETWEEN,
+            &layout,
+            options.clone().overflow(crate::Overflow::ELLIPSIS)
+        ),
+        String::from(">>>>>  >>>…\nquote    ok")
+    );
+    assert_eq!(
+        try_columns(
+            text,
+            Spacing::BETWEEN,
+            &layout,
+            options.overflow(crate::Overflow::ERROR)
+        ),
+        Err(Error::LineTooWide {
+            width: 4,
+            actual: 6
+        })
+    );
+}
+
+#[test]
+fn test_columns_auto() {
+    let text = ["Name:", "A very long description that needs to be wrapped."];
+
+    let layout = Layout::from_pattern(">a <-").unwrap();
+
+    let expected = String::from(
+        "Name: A very long  \n      description  \n      that needs to\n      be wrapped.  ",
+    );
+
+    assert_eq!(columns(text, Spacing::BETWEEN, &layout, 19), expected);
+
+    // the short column keeps its width when the other one shrinks
+    let long =
+        "The automatic columns are shrunk from the widest one first, so the labels stay whole.";
+    let labelled = try_columns(
+        ["Name:", long],
+        Spacing::BETWEEN,
+        &Layout::from_pattern("a a").unwrap(),
+        40,
+    )
+    .unwrap();
+    assert!(labelled.starts_with("Name: The automatic columns are shrunk "));
+    assert!(labelled
+        .lines()
+        .skip(1)
+        .all(|line| line.starts_with("      ")));
+
+    assert_eq!(
+        try_columns(
+            ["a", "abcdefghij klmnopqrst uvwxyzabcd efghijklm"],
+            Spacing::BETWEEN,
+            &Layout::from_pattern("a a").unwrap(),
+            10
+        ),
+        Ok(String::from(
+            "a abcdefgh\n  ij      \n  klmnopqr\n  st      \n  uvwxyzab\n  cd      \n  efghijkl\n  m       "
+        ))
+    );
+}
+
+#[test]
+fn test_columns_vertical_alignment() {
+    let text = ["one\ntwo\nthree\nfour", "top", "middle", "bottom"];
+
+    let layout = Layout::from_pattern("5 - ~- _-").unwrap();
+
+    let expected = String::from(
+        "one   top                    \ntwo           middle         \nthree                        \nfour                  bottom ",
+    );
+
+    assert_eq!(columns(text, Spacing::BETWEEN, &layout, 29), expected);
+}
+
+#[test]
+fn test_columns_lines() {
+    let layout = Layout::from_pattern("< >").unwrap();
+    let text = ["one two three", "four five"];
+
+    // the same lines as `columns`
+    let lines: Vec<String> = columns_lines(text, Spacing::BETWEEN, &layout, 11)
+        .unwrap()
+        .map(|line| line.unwrap())
+        .collect();
+    assert_eq!(
+        lines.join("\n"),
+        columns(text, Spacing::BETWEEN, &layout, 11)
+    );
+
+    let mut output = String::new();
+    columns_lines(text, Spacing::BETWEEN, &layout, 11)
+        .unwrap()
+        .write_to(&mut output)
+        .unwrap();
+    assert_eq!(output, "one    four\ntwo    five\nthree      \n");
+
+    assert_eq!(
+        columns_lines(text, Spacing::AROUND, &layout, 2).err(),
+        Some(Error::NotEnoughWidth {
+            available: 2,
+            needed: 3
+        })
+    );
+}
+
+#[test]
+fn test_columns_justification() {
+    let layout = Layout::from_pattern("= =").unwrap();
+    let text = ["a b c d e f g h i j k", "a b c d e f g h i j k"];
+    let options = crate::Options::new(17).justification(crate::Justification::ALTERNATING);
+
+    assert_eq!(
+        columns(text, Spacing::BETWEEN, &layout, options),
+        "a  b c d a  b c d\ne f g  h e f g  h\ni j k    i j k   "
+    );
+
+    // the lines of a column aligned to the bottom are
+    // justified the same way as on their own
+    let layout = Layout::from_pattern("=8 =_8").unwrap();
+    let options = crate::Options::new(16).justification(crate::Justification::ALTERNATING);
+    let output = columns(
+        ["a b c d e f g h i j k", "a b c d e f"],
+        Spacing::NONE,
+        &layout,
+        options,
+    );
+    let bottom: Vec<&str> = output.lines().map(|line| &line[8..]).collect();
+    let alone = crate::Options::new(8).justification(crate::Justification::ALTERNATING);
+    let aligned = crate::align("a b c d e f", crate::Alignment::JUSTIFY, alone);
+    assert_eq!(bottom[1..], aligned.lines().collect::<Vec<&str>>());
+}
+
+#[test]
+fn test_columns_last_line() {
+    let layout = Layout::from_pattern("= =").unwrap();
+    let text = ["a b c d e f", "a b c d e f"];
+    let options = crate::Options::new(17).last_line(crate::LastLine::RIGHT);
+
+    assert_eq!(
+        columns(text, Spacing::BETWEEN, &layout, options),
+        "a b  c d a b  c d\n     e f      e f"
+    );
+}
+
+#[test]
+fn test_columns_paragraphs() {
+    let layout = Layout::from_pattern("= =").unwrap();
+    let text = ["a b c d e f", "a b c d e f g h i j k"];
+
+    // the last line of each column isn't justified, even if the other is longer
+    assert_eq!(
+        columns(text, Spacing::BETWEEN, &layout, 17),
+        "a b  c d a b  c d\ne f      e f  g h\n         i j k   "
+    );
+}
+
+#[test]
+fn test_columns_anchor() {
+    let layout = Layout::from_pattern("< .").unwrap();
+    let text = ["pi\ne\nanswer", "3.14\n2.718\n42"];
+
+    assert_eq!(
+        columns(text, Spacing::BETWEEN, &layout, 17),
+        "pi          3.14 \ne           2.718\nanswer     42    "
+    );
+
+    // too narrow to line the values up, they are aligned to the right
+    let options = crate::Options::new(20).overflow(crate::Overflow::TRUNCATE);
+    assert_eq!(
+        try_columns(
+            ["x", "1.5\n12345\n2.25"],
+            Spacing::BETWEEN,
+            &Layout::from_pattern("< .5").unwrap(),
+            options.clone()
+        ),
+        Ok(String::from(
+            "x                1.5\n               12345\n                2.25"
+        ))
+    );
+    // the automatic width fits the lined up values
+    assert_eq!(
+        try_columns(
+            ["x", "1.5\n12345\n2.25"],
+            Spacing::BETWEEN,
+            &Layout::from_pattern("< .a").unwrap(),
+            options
+        ),
+        Ok(String::from(
+            "x               1.5 \n            12345   \n                2.25"
+        ))
+    );
+}
+
+#[test]
+fn test_columns_tabs() {
+    let layout = Layout::from_pattern("< a").unwrap();
+    let options = crate::Options::new(20).tabs(crate::Tabs::EXPAND(4));
+
+    // the tabs are expanded from the start of each cell
+    assert_eq!(
+        columns(
+            ["x\ty", "key\tvalue"],
+            Spacing::NONE,
+            &layout,
+            options.clone()
+        ),
+        "x   y      key value"
+    );
+    assert_eq!(
+        try_columns(
+            ["x\ty", "z"],
+            Spacing::NONE,
+            &layout,
+            options.tabs(crate::Tabs::ERROR)
+        ),
+        Err(Error::TabCharacter { position: 1 })
+    );
+}
+
+#[test]
+fn test_columns_direction() {
+    let layout = Layout::from_pattern("<3 >-").unwrap();
+    let options = crate::Options::new(9).direction(Direction::RTL);
+
+    // the first column is on the right
+    assert_eq!(
+        columns(["one", "two"], Spacing::BETWEEN, &layout, options),
+        "  two one"
+    );
+}
+
+#[test]
+fn test_columns_measure() {
+    let layout = Layout::from_pattern("<a >-").unwrap();
+    let options = crate::Options::new(10).measure(&crate::CjkWidth);
+
+    // the automatic column is sized with the measure
+    assert_eq!(
+        columns(["±±", "1"], Spacing::BETWEEN, &layout, options),
+        "±±     1"
+    );
+}
+
+#[test]
+fn test_columns_graphemes() {
+    let family = "👨\u{200d}👩\u{200d}👧";
+    let layout = Layout::from_pattern("<3 <-").unwrap();
+
+    // long words are broken between the clusters
+    assert_eq!(
+        columns([&family.repeat(2), "b"], Spacing::BETWEEN, &layout, 6),
+        format!("{}  b \n{}    ", family, family)
+    );
+}
+
+#[test]
+fn test_columns_fill() {
+    let layout = Layout::from_pattern("<-(.) >2").unwrap();
+
+    assert_eq!(
+        columns(["Chapter 1", "12"], Spacing::BETWEEN, &layout, 21),
+        "Chapter 1 ........ 12"
+    );
+}
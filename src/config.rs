@@ -0,0 +1,226 @@
+// serde support, to keep layouts in configuration files
+
+use std::fmt;
+
+use serde::de::{self, SeqAccess, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::Alignment;
+use crate::Error;
+use crate::Layout;
+use crate::Spacing;
+use crate::VerticalAlignment;
+
+// a column of the structured form of a layout
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ColumnConfig {
+    #[serde(default)]
+    unit: Unit,
+    size: Option<usize>,
+    #[serde(default)]
+    alignment: Alignment,
+    #[serde(default)]
+    vertical_alignment: VerticalAlignment,
+    min: Option<usize>,
+    max: Option<usize>,
+    fill: Option<String>,
+    #[serde(default)]
+    repeat: bool,
+}
+
+#[derive(Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum Unit {
+    #[default]
+    FRACTIONAL,
+    FIXED,
+    PERCENT,
+    AUTO,
+}
+
+impl ColumnConfig {
+    // adds the column to a layout (without the repeating flag)
+    fn add_to(&self, layout: Layout) -> Layout {
+        let mut layout = match self.unit {
+            Unit::FRACTIONAL => layout.fractional(self.size.unwrap_or(1), self.alignment),
+            Unit::FIXED => layout.fixed(self.size.unwrap_or(0), self.alignment),
+            Unit::PERCENT => layout.percent(self.size.unwrap_or(0), self.alignment),
+            Unit::AUTO => layout.auto(self.alignment),
+        };
+        layout = layout.vertical_align(self.vertical_alignment);
+        if let Some(min) = self.min {
+            layout = layout.min_width(min);
+        }
+        if let Some(max) = self.max {
+            layout = layout.max_width(max);
+        }
+        if let Some(fill) = &self.fill {
+            layout = layout.fill(fill);
+        }
+        return layout;
+    }
+}
+
+// builds a layout from its columns, through the equivalent pattern
+// so that the errors are the same as with `Layout::from_pattern`
+fn build_layout(columns: &[ColumnConfig]) -> Result<Layout, Error> {
+    let written: Vec<String> = columns
+        .iter()
+        .map(|column| {
+            let token = column.add_to(Layout::new()).to_string();
+            if column.repeat {
+                token + "*"
+            } else {
+                token
+            }
+        })
+        .collect();
+    return Layout::from_pattern(&written.join(" "));
+}
+
+struct LayoutVisitor;
+
+impl<'de> Visitor<'de> for LayoutVisitor {
+    type Value = Layout;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("a layout pattern or a list of columns")
+    }
+
+    fn visit_str<E: de::Error>(self, pattern: &str) -> Result<Layout, E> {
+        Layout::from_pattern(pattern).map_err(E::custom)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Layout, A::Error> {
+        let mut columns = Vec::new();
+        while let Some(column) = seq.next_element::<ColumnConfig>()? {
+            if column.size.is_none() && matches!(column.unit, Unit::FIXED | Unit::PERCENT) {
+                return Err(de::Error::missing_field("size"));
+            }
+            if column.size.is_some() && column.unit == Unit::AUTO {
+                return Err(de::Error::custom("an automatic column can't have a `size`"));
+            }
+            columns.push(column);
+        }
+        build_layout(&columns).map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Layout {
+    /// Serializes the layout as a pattern, see [Layout::from_pattern].
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Layout {
+    /// Deserializes a layout from a pattern (see [Layout::from_pattern]),
+    /// or from a list of columns.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(LayoutVisitor)
+    }
+}
+
+impl Serialize for Alignment {
+    /// Serializes the alignment as in a pattern, like `<` or `@;`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Alignment {
+    /// Deserializes an alignment as written in a pattern, or its name.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let token = String::deserialize(deserializer)?;
+        token.parse().map_err(de::Error::custom)
+    }
+}
+
+impl Serialize for Spacing {
+    /// Serializes the spacing as `none`, `between` or `around`.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Spacing {
+    /// Deserializes `none`, `between` or `around`, ignoring the case.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let token = String::deserialize(deserializer)?;
+        token.parse().map_err(de::Error::custom)
+    }
+}
+
+#[test]
+fn test_layout_pattern() {
+    let layout: Layout = serde_json::from_str(r#""^5 <-*""#).unwrap();
+    assert_eq!(layout, Layout::from_pattern("^5 <-*").unwrap());
+    assert_eq!(serde_json::to_string(&layout).unwrap(), r#""^5 <-*""#);
+
+    // the same errors as the patterns
+    let error = serde_json::from_str::<Layout>(r#""<- ?""#).unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        Layout::from_pattern("<- ?").unwrap_err().to_string() + " at line 1 column 6"
+    );
+}
+
+#[test]
+fn test_layout_columns() {
+    let json = r#"[
+        { "unit": "fixed", "size": 5, "alignment": "^" },
+        { "alignment": "right", "vertical_alignment": "bottom", "min": 3, "fill": ".", "repeat": true },
+        { "unit": "percent", "size": 20 },
+        { "unit": "auto", "alignment": "@;" }
+    ]"#;
+    let layout: Layout = serde_json::from_str(json).unwrap();
+    assert_eq!(
+        layout,
+        Layout::from_pattern("^5 >_-[3..](.)* <20% @;a").unwrap()
+    );
+
+    let duplicate = r#"[{ "size": 2, "repeat": true }, { "repeat": true }]"#;
+    let error = serde_json::from_str::<Layout>(duplicate).unwrap_err();
+    assert!(error
+        .to_string()
+        .starts_with(&Layout::from_pattern("<--* <-*").unwrap_err().to_string()));
+
+    let missing = r#"[{ "unit": "fixed" }]"#;
+    assert!(serde_json::from_str::<Layout>(missing)
+        .unwrap_err()
+        .to_string()
+        .starts_with("missing field `size`"));
+
+    let sized = r#"[{ "unit": "auto", "size": 3 }]"#;
+    assert!(serde_json::from_str::<Layout>(sized)
+        .unwrap_err()
+        .to_string()
+        .starts_with("an automatic column can't have a `size`"));
+
+    let unknown = r#"[{ "aligment": "<" }]"#;
+    assert!(serde_json::from_str::<Layout>(unknown).is_err());
+}
+
+#[test]
+fn test_alignment_spacing() {
+    assert_eq!(
+        serde_json::to_string(&Alignment::JUSTIFY).unwrap(),
+        r#""=""#
+    );
+    assert_eq!(
+        serde_json::from_str::<Alignment>(r#""center""#).unwrap(),
+        Alignment::CENTER
+    );
+    assert!(serde_json::from_str::<Alignment>(r#""<<""#).is_err());
+
+    assert_eq!(
+        serde_json::to_string(&Spacing::AROUND).unwrap(),
+        r#""around""#
+    );
+    assert_eq!(
+        serde_json::from_str::<Spacing>(r#""between""#).unwrap(),
+        Spacing::BETWEEN
+    );
+    assert!(serde_json::from_str::<Spacing>(r#""wide""#).is_err());
+}
@@ -0,0 +1,321 @@
+use std::fmt;
+
+use crate::ansi;
+use crate::UnicodeWidth;
+
+/// Errors returned when building or using a [Layout](crate::Layout).
+#[derive(Clone, Debug, PartialEq)]
+#[non_exhaustive]
+pub enum Error {
+    /// A column of a pattern couldn't be parsed.
+    InvalidColumn {
+        /// The invalid column, as written in the pattern
+        token: String,
+        /// The position of the column in the pattern, in bytes
+        position: usize,
+        /// The position of the unexpected character in the pattern, in bytes
+        offset: usize,
+        /// What was expected instead
+        expected: String,
+    },
+    /// More than one column of a pattern is marked as repeating.
+    DuplicateRepeat {
+        /// The second repeating column, as written in the pattern
+        token: String,
+        /// The position of the column in the pattern, in bytes
+        position: usize,
+    },
+    /// A layout that already has a repeating column was
+    /// given another one with [Layout::try_repeat](crate::Layout::try_repeat).
+    AlreadyRepeating {
+        /// The index of the column that couldn't be repeated, starting at 0
+        /// and counting the repeating column once
+        index: usize,
+    },
+    /// There are less columns than the layout needs.
+    TooFewColumns {
+        /// The minimum number of columns of the layout
+        expected: usize,
+        /// The number of columns requested
+        actual: usize,
+    },
+    /// There are more columns than the layout can hold.
+    TooManyColumns {
+        /// The maximum number of columns of the layout
+        expected: usize,
+        /// The number of columns requested
+        actual: usize,
+    },
+    /// The width is too small to fit the spacing or the borders
+    /// between the columns.
+    NotEnoughWidth {
+        /// The width available
+        available: usize,
+        /// The width taken by the spacing or the borders
+        needed: usize,
+    },
+    /// A line is wider than the space available, and the overflow
+    /// policy is [Overflow::ERROR](crate::Overflow::ERROR).
+    LineTooWide {
+        /// The space available
+        width: usize,
+        /// The width of the line
+        actual: usize,
+    },
+    /// A column ended up with no space for its content.
+    ZeroWidthColumn {
+        /// The index of the column, starting at 0
+        index: usize,
+    },
+    /// The text contains a tab, and the tab policy is
+    /// [Tabs::ERROR](crate::Tabs::ERROR).
+    TabCharacter {
+        /// The position of the first tab in the text, in bytes
+        position: usize,
+    },
+    /// The layout has no columns.
+    EmptyLayout,
+    /// A string couldn't be parsed as an [Alignment](crate::Alignment).
+    InvalidAlignment {
+        /// The invalid string
+        token: String,
+    },
+    /// A string couldn't be parsed as a [Spacing](crate::Spacing).
+    InvalidSpacing {
+        /// The invalid string
+        token: String,
+    },
+    /// The output couldn't be written into a sink.
+    Write {
+        /// The description of the underlying error
+        message: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidColumn {
+                token,
+                position,
+                offset,
+                expected,
+            } => write!(
+                f,
+                "Invalid column [{}] at position {}: expected {} at position {}",
+                token, position, expected, offset
+            ),
+            Error::DuplicateRepeat { token, position } => write!(
+                f,
+                "Only one column can be repeated, found another one [{}] at position {}",
+                token, position
+            ),
+            Error::AlreadyRepeating { index } => write!(
+                f,
+                "Only one column can be repeated, the column at index {} can't be",
+                index
+            ),
+            Error::TooFewColumns { expected, actual } => write!(
+                f,
+                "Not enough columns: expected at least {}, got {}",
+                expected, actual
+            ),
+            Error::TooManyColumns { expected, actual } => write!(
+                f,
+                "Too many columns: expected at most {}, got {}",
+                expected, actual
+            ),
+            Error::NotEnoughWidth { available, needed } => write!(
+                f,
+                "Not enough width: {} is needed around the columns, got {}",
+                needed, available
+            ),
+            Error::LineTooWide { width, actual } => write!(
+                f,
+                "Line too wide: the width is {}, got a line of {}",
+                width, actual
+            ),
+            Error::ZeroWidthColumn { index } => {
+                write!(f, "The column at index {} has a width of zero", index)
+            }
+            Error::TabCharacter { position } => {
+                write!(f, "Unexpected tab character at position {}", position)
+            }
+            Error::EmptyLayout => write!(f, "The layout has no columns"),
+            Error::InvalidAlignment { token } => write!(f, "Invalid alignment [{}]", token),
+            Error::InvalidSpacing { token } => write!(f, "Invalid spacing [{}]", token),
+            Error::Write { message } => write!(f, "Couldn't write the output: {}", message),
+        }
+    }
+}
+
+impl Error {
+    /// Points at the error in the pattern given to
+    /// [Layout::from_pattern](crate::Layout::from_pattern).
+    ///
+    /// The pattern is followed by a line with a caret `^` under the
+    /// character where parsing stopped and what was expected there.
+    /// Returns `None` for the errors that don't come from a pattern.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use textflow::Layout;
+    /// let pattern = "^7 8[2] 9";
+    /// let error = Layout::from_pattern(pattern).unwrap_err();
+    /// assert_eq!(
+    ///     error.diagnostic(pattern).unwrap(),
+    ///     "^7 8[2] 9\n      ^ expected `..`"
+    /// );
+    /// ```
+    pub fn diagnostic(&self, pattern: &str) -> Option<String> {
+        let (offset, message) = match self {
+            Error::InvalidColumn {
+                offset, expected, ..
+            } => (*offset, format!("expected {}", expected)),
+            Error::DuplicateRepeat { position, .. } => {
+                (*position, String::from("only one column can be repeated"))
+            }
+            _ => return None,
+        };
+
+        let before = pattern.get(..offset)?;
+        let indent = ansi::display_width(before, &UnicodeWidth);
+        return Some(format!("{}\n{}^ {}", pattern, " ".repeat(indent), message));
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<fmt::Error> for Error {
+    fn from(error: fmt::Error) -> Self {
+        Error::Write {
+            message: error.to_string(),
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Self {
+        Error::Write {
+            message: error.to_string(),
+        }
+    }
+}
+
+#[test]
+fn test_display() {
+    assert_eq!(
+        Error::InvalidColumn {
+            token: String::from("8[2]"),
+            position: 3,
+            offset: 6,
+            expected: String::from("`..`"),
+        }
+        .to_string(),
+        "Invalid column [8[2]] at position 3: expected `..` at position 6"
+    );
+    assert_eq!(
+        Error::DuplicateRepeat {
+            token: String::from("9*"),
+            position: 6
+        }
+        .to_string(),
+        "Only one column can be repeated, found another one [9*] at position 6"
+    );
+    assert_eq!(
+        Error::AlreadyRepeating { index: 2 }.to_string(),
+        "Only one column can be repeated, the column at index 2 can't be"
+    );
+    assert_eq!(
+        Error::TooFewColumns {
+            expected: 3,
+            actual: 1
+        }
+        .to_string(),
+        "Not enough columns: expected at least 3, got 1"
+    );
+    assert_eq!(
+        Error::TooManyColumns {
+            expected: 3,
+            actual: 5
+        }
+        .to_string(),
+        "Too many columns: expected at most 3, got 5"
+    );
+    assert_eq!(
+        Error::NotEnoughWidth {
+            available: 2,
+            needed: 4
+        }
+        .to_string(),
+        "Not enough width: 4 is needed around the columns, got 2"
+    );
+    assert_eq!(
+        Error::LineTooWide {
+            width: 10,
+            actual: 12
+        }
+        .to_string(),
+        "Line too wide: the width is 10, got a line of 12"
+    );
+    assert_eq!(
+        Error::ZeroWidthColumn { index: 1 }.to_string(),
+        "The column at index 1 has a width of zero"
+    );
+    assert_eq!(
+        Error::TabCharacter { position: 7 }.to_string(),
+        "Unexpected tab character at position 7"
+    );
+    assert_eq!(Error::EmptyLayout.to_string(), "The layout has no columns");
+    assert_eq!(
+        Error::InvalidAlignment {
+            token: String::from("?")
+        }
+        .to_string(),
+        "Invalid alignment [?]"
+    );
+    assert_eq!(
+        Error::InvalidSpacing {
+            token: String::from("wide")
+        }
+        .to_string(),
+        "Invalid spacing [wide]"
+    );
+    assert_eq!(
+        Error::from(fmt::Error).to_string(),
+        "Couldn't write the output: an error occurred when formatting an argument"
+    );
+}
+
+#[test]
+fn test_diagnostic() {
+    let invalid = Error::InvalidColumn {
+        token: String::from("+8"),
+        position: 3,
+        offset: 3,
+        expected: String::from("a width"),
+    };
+    assert_eq!(
+        invalid.diagnostic("^7 +8 9"),
+        Some(String::from("^7 +8 9\n   ^ expected a width"))
+    );
+    // the caret is placed under the character, even after wide ones
+    assert_eq!(
+        invalid.diagnostic("你+8"),
+        Some(String::from("你+8\n  ^ expected a width"))
+    );
+    assert_eq!(
+        Error::DuplicateRepeat {
+            token: String::from("9*"),
+            position: 4
+        }
+        .diagnostic("^7* 9*"),
+        Some(String::from(
+            "^7* 9*\n    ^ only one column can be repeated"
+        ))
+    );
+    // not from this pattern
+    assert_eq!(invalid.diagnostic("<"), None);
+    assert_eq!(Error::EmptyLayout.diagnostic(""), None);
+}
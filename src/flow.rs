@@ -0,0 +1,293 @@
+use crate::ansi;
+use crate::columns::join_grid;
+use crate::lines::{anchor_extents, place_anchor};
+use crate::utils::*;
+
+use crate::Direction;
+use crate::Error;
+use crate::Gutters;
+use crate::Layout;
+
+/// Wraps a single text and makes it flow through several columns,
+/// like in a newspaper.
+///
+/// There is one column for each column of the layout, the repeating
+/// one being used once: a layout like `= = =` gives three justified
+/// columns. The text is wrapped once, to the width of the narrowest
+/// column, and the lines are split between the columns so that their
+/// heights are as even as possible, the first columns being the longest.
+///
+/// `spacing_or_gutters` and `width_or_options` work the same way as
+/// with [columns()](crate::columns()).
+///
+/// # Examples
+///
+/// ```
+/// use textflow::flow_columns;
+/// use textflow::Layout;
+/// use textflow::Spacing;
+///
+/// fn main() {
+///     let text = "Academy City was developed from the undeveloped land of western Tokyo.";
+///     let layout = Layout::from_pattern("< <").unwrap();
+///     println!("{}", flow_columns(text, Spacing::BETWEEN, &layout, 29));
+/// }
+/// ```
+/// should display
+/// ```text
+/// Academy City   undeveloped
+/// was developed  land of
+/// from the       western Tokyo.
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the width is too small to fit the columns,
+/// if a line is too wide and the overflow policy is
+/// [Overflow::ERROR](crate::Overflow::ERROR) or if the text contains a tab
+/// and the tab policy is [Tabs::ERROR](crate::Tabs::ERROR).
+/// Use [try_flow_columns()] to handle these errors instead.
+pub fn flow_columns<
+    'a,
+    TextwrapAlgo,
+    TextwrapWordSep,
+    TextwrapWordSplit,
+    TextwrapOptions,
+    SpacingOrGutters,
+>(
+    text: &str,
+    spacing_or_gutters: SpacingOrGutters,
+    layout: &Layout,
+    width_or_options: TextwrapOptions,
+) -> String
+where
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    SpacingOrGutters: Into<Gutters>,
+{
+    try_flow_columns(text, spacing_or_gutters, layout, width_or_options).unwrap()
+}
+
+/// Makes a text flow through several columns, without panicking.
+///
+/// This is the same as [flow_columns()], but returns an [Error] when the
+/// columns can't be printed, like [try_columns()](crate::try_columns()) does.
+pub fn try_flow_columns<
+    'a,
+    TextwrapAlgo,
+    TextwrapWordSep,
+    TextwrapWordSplit,
+    TextwrapOptions,
+    SpacingOrGutters,
+>(
+    text: &str,
+    spacing_or_gutters: SpacingOrGutters,
+    layout: &Layout,
+    width_or_options: TextwrapOptions,
+) -> Result<String, Error>
+where
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+    SpacingOrGutters: Into<Gutters>,
+{
+    let options = width_or_options.into();
+    let gutters = spacing_or_gutters.into();
+
+    let count = layout.column_count();
+
+    let needed = spacing_needed(&gutters, count, options.measure);
+    let available = options
+        .wrap
+        .width
+        .checked_sub(needed)
+        .ok_or(Error::NotEnoughWidth {
+            available: options.wrap.width,
+            needed,
+        })?;
+
+    let columns = layout.resolve(available, count)?;
+
+    if columns.is_empty() {
+        // nothing to print
+        return Ok(String::new());
+    }
+    if let Some(index) = columns.iter().position(|column| column.width == 0) {
+        return Err(Error::ZeroWidthColumn { index });
+    }
+
+    // the lines must fit in every column
+    let narrowest = columns.iter().map(|column| column.width).min().unwrap();
+    let wrapped = wrap_text(
+        text,
+        &copy_textwrap_options(&options.wrap, narrowest),
+        options.tabs,
+        options.measure,
+    )?;
+
+    if wrapped.iter().all(|(line, _)| line.is_empty()) {
+        // no visible text, like with no columns
+        return Ok(String::new());
+    }
+
+    // the first columns get the remaining lines
+    let longer = wrapped.len() % columns.len();
+    let height = wrapped.len() / columns.len() + usize::from(longer > 0);
+
+    // the column of each line, and its position in the column
+    let mut positions = Vec::new();
+    for column in 0..columns.len() {
+        let column_height = if longer == 0 || column < longer {
+            height
+        } else {
+            height - 1
+        };
+        for row in 0..column_height {
+            positions.push((column, row));
+        }
+    }
+
+    let anchors: Vec<(usize, usize)> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let lines = wrapped.iter().zip(positions.iter());
+            let lines = lines.filter(|(_, (line_column, _))| *line_column == i);
+            anchor_extents(
+                lines.map(|((line, _), _)| line.as_ref()),
+                column,
+                options.measure,
+            )
+        })
+        .collect();
+
+    // the shorter columns are left blank at the bottom
+    let mut grid: Vec<Vec<String>> = (0..height)
+        .map(|_| {
+            columns
+                .iter()
+                .map(|column| " ".repeat(column.width))
+                .collect()
+        })
+        .collect();
+    // style carried over from the previous lines
+    let mut style = String::new();
+
+    for (i, ((line, last_line), (column, row))) in wrapped.iter().zip(positions).enumerate() {
+        let styled = ansi::reopen_style(line, &style);
+        style = ansi::active_style(line, &style);
+        let (styled, alignment) =
+            place_anchor(styled, &columns[column], anchors[column], options.measure);
+
+        grid[row][column] = crate::align::align_line(
+            &styled,
+            columns[column].width,
+            alignment,
+            *last_line,
+            i,
+            options.line_settings().for_column(&columns[column]),
+        )?;
+    }
+
+    if options.direction == Direction::RTL {
+        // the text starts in the rightmost column
+        for line in grid.iter_mut() {
+            line.reverse();
+        }
+    }
+
+    return Ok(join_grid(&grid, &gutters));
+}
+
+#[test]
+fn test_flow_columns() {
+    // the doctest
+    let text = "Academy City was developed from the undeveloped land of western Tokyo.";
+
+    let layout = Layout::from_pattern("< <").unwrap();
+
+    let expected = String::from(
+        "Academy City   undeveloped   \nwas developed  land of       \nfrom the       western Tokyo.",
+    );
+
+    assert_eq!(
+        flow_columns(text, crate::Spacing::BETWEEN, &layout, 29),
+        expected
+    );
+}
+
+#[test]
+fn test_flow_columns_balance() {
+    let text = "a b c d e f g";
+
+    let layout = Layout::from_pattern("1 >1 =1").unwrap();
+
+    // 7 lines in 3 columns
+    let expected = String::from("a d f\nb e g\nc    ");
+
+    assert_eq!(
+        flow_columns(text, crate::Spacing::BETWEEN, &layout, 5),
+        expected
+    );
+
+    // the repeating column is used once
+    let repeating = Layout::from_pattern("- -*").unwrap();
+
+    assert_eq!(
+        flow_columns("one two", crate::Spacing::NONE, &repeating, 6),
+        String::from("onetwo")
+    );
+}
+
+#[test]
+fn test_flow_columns_justify() {
+    let text = "the lines are justified across columns but not the last";
+
+    let layout = Layout::from_pattern("= =").unwrap();
+
+    // only the very last line is left aligned
+    let expected = String::from(
+        "the     lines | columns   but\nare justified | not the last \nacross        |              ",
+    );
+
+    assert_eq!(
+        flow_columns(text, crate::Gutters::new().between(" | "), &layout, 29),
+        expected
+    );
+}
+
+#[test]
+fn test_try_flow_columns() {
+    let layout = Layout::from_pattern("< <").unwrap();
+
+    assert_eq!(
+        try_flow_columns("text", crate::Spacing::AROUND, &layout, 2),
+        Err(Error::NotEnoughWidth {
+            available: 2,
+            needed: 3
+        })
+    );
+    assert_eq!(
+        try_flow_columns("text", crate::Spacing::NONE, &Layout::new(), 10),
+        Ok(String::new())
+    );
+    assert_eq!(
+        try_flow_columns("", crate::Spacing::BETWEEN, &layout, 10),
+        Ok(String::new())
+    );
+}
+
+#[test]
+fn test_flow_columns_direction() {
+    let layout = Layout::from_pattern("- -").unwrap();
+    let options = crate::Options::new(2).direction(Direction::RTL);
+
+    // the text starts in the rightmost column
+    assert_eq!(
+        flow_columns("a b c d", crate::Spacing::NONE, &layout, options),
+        "ca\ndb"
+    );
+}
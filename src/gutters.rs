@@ -0,0 +1,156 @@
+use std::fmt;
+use std::str::FromStr;
+
+use crate::Error;
+use crate::Spacing;
+
+/// Custom spacing between and around columns.
+///
+/// The margins can be any string, for example to draw lines
+/// between the columns. Everywhere `Gutters` are expected,
+/// you can also use a [Spacing].
+///
+/// # Examples
+///
+/// ```
+/// use textflow::{columns, Gutters, Layout};
+///
+/// let gutters = Gutters::new().left("│ ").between(" │ ").right(" │");
+///
+/// assert_eq!(
+///     columns(["one", "two"], gutters, &Layout::default(), 13),
+///     "│ one │ two │"
+/// );
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Gutters {
+    left: String,
+    between: String,
+    right: String,
+}
+
+impl Gutters {
+    /// Creates gutters without any margins (the same as [Spacing::NONE]).
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the margin before the first column.
+    pub fn left(mut self, margin: &str) -> Self {
+        self.left = String::from(margin);
+        return self;
+    }
+
+    /// Set the margin between the columns.
+    pub fn between(mut self, margin: &str) -> Self {
+        self.between = String::from(margin);
+        return self;
+    }
+
+    /// Set the margin after the last column.
+    pub fn right(mut self, margin: &str) -> Self {
+        self.right = String::from(margin);
+        return self;
+    }
+
+    // the margin to put before the column at `index`
+    pub(crate) fn before(&self, index: usize) -> &str {
+        if index == 0 {
+            &self.left
+        } else {
+            &self.between
+        }
+    }
+
+    // the margin to put after the last column
+    pub(crate) fn after(&self) -> &str {
+        &self.right
+    }
+
+    // the margins, in order
+    pub(crate) fn margins(&self) -> [&str; 3] {
+        [&self.left, &self.between, &self.right]
+    }
+}
+
+impl FromStr for Spacing {
+    type Err = Error;
+
+    /// Parses `none`, `between` or `around`, ignoring the case.
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        match token.to_ascii_lowercase().as_str() {
+            "none" => Ok(Spacing::NONE),
+            "between" => Ok(Spacing::BETWEEN),
+            "around" => Ok(Spacing::AROUND),
+            _ => Err(Error::InvalidSpacing {
+                token: String::from(token),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Spacing {
+    /// Writes `none`, `between` or `around`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Spacing::NONE => f.write_str("none"),
+            Spacing::BETWEEN => f.write_str("between"),
+            Spacing::AROUND => f.write_str("around"),
+        }
+    }
+}
+
+impl From<Spacing> for Gutters {
+    fn from(spacing: Spacing) -> Self {
+        match spacing {
+            Spacing::NONE => Gutters::new(),
+            Spacing::BETWEEN => Gutters::new().between(" "),
+            Spacing::AROUND => Gutters::new().left(" ").between(" ").right(" "),
+        }
+    }
+}
+
+impl From<&Gutters> for Gutters {
+    fn from(gutters: &Gutters) -> Self {
+        gutters.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder() {
+        let gutters = Gutters::new().left("[").between("|").right("]");
+
+        assert_eq!(gutters.before(0), "[");
+        assert_eq!(gutters.before(1), "|");
+        assert_eq!(gutters.before(7), "|");
+        assert_eq!(gutters.after(), "]");
+    }
+
+    #[test]
+    fn test_from_spacing() {
+        assert_eq!(Gutters::from(Spacing::NONE), Gutters::new());
+        assert_eq!(Gutters::from(Spacing::BETWEEN), Gutters::new().between(" "));
+        assert_eq!(
+            Gutters::from(Spacing::AROUND),
+            Gutters::new().left(" ").between(" ").right(" ")
+        );
+    }
+
+    #[test]
+    fn test_spacing_str() {
+        for spacing in [Spacing::NONE, Spacing::BETWEEN, Spacing::AROUND] {
+            assert_eq!(spacing.to_string().parse(), Ok(spacing));
+        }
+        assert_eq!("Around".parse(), Ok(Spacing::AROUND));
+        assert_eq!(
+            "wide".parse::<Spacing>(),
+            Err(Error::InvalidSpacing {
+                token: String::from("wide")
+            })
+        );
+    }
+}
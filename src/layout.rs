@@ -1,14 +1,50 @@
+use crate::align::anchor_extent;
+use crate::pattern::{self, escape, parse_alignment};
 use crate::utils::*;
 
 use crate::Alignment;
-use lazy_static::lazy_static;
-use regex::Regex;
+use crate::Error;
+use crate::VerticalAlignment;
+use crate::WidthMeasure;
+use std::fmt;
+use std::str::FromStr;
 
 /// Dynamic columns layout.
 ///
 /// Layouts can support a variable number of columns
 /// (see [`Layout::repeat`]) and have columns with dynamic
-/// size (see [`Layout::fractioanl`]).
+/// size (see [`Layout::fractional`]).
+///
+/// A layout can be written as a pattern and read back with
+/// [Layout::from_pattern] (or `parse`), and turned back into
+/// its pattern with `to_string`:
+///
+/// ```
+/// use textflow::Layout;
+///
+/// let layout: Layout = "^5 <[3..](.) >-*".parse().unwrap();
+/// assert_eq!(layout.to_string(), "^5 <-[3..](.) >-*");
+/// assert_eq!(layout.to_string().parse(), Ok(layout));
+/// ```
+///
+/// With the `serde` feature, a layout is serialized as its pattern. It can be
+/// deserialized from a pattern or from a list of columns, each one with the
+/// optional fields `unit` (`fractional`, `fixed`, `percent` or `auto`), `size`,
+/// `alignment` (like `<` or `center`), `vertical_alignment` (`top`, `middle`
+/// or `bottom`), `min`, `max`, `fill` and `repeat` (`size` is required for
+/// fixed and percentage columns, and not allowed for automatic ones). The
+/// errors are the same as with the equivalent pattern. For example, in TOML:
+///
+/// ```toml
+/// [[layout]]
+/// unit = "fixed"
+/// size = 5
+/// alignment = "center"
+///
+/// [[layout]]
+/// repeat = true
+/// ```
+#[derive(Clone, Debug, PartialEq)]
 pub struct Layout {
     // columns before the repeating one
     left: Vec<DynCol>,
@@ -19,23 +55,45 @@ pub struct Layout {
 }
 
 // calculated layout column
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct Column {
     pub width: usize,
     pub alignment: Alignment,
+    pub vertical_alignment: VerticalAlignment,
+    // overrides the fill pattern of the options
+    pub fill: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 struct DynCol {
     size: usize,
     unit: ColWidthUnit,
     align: Alignment,
+    valign: VerticalAlignment,
+    // constraints on the calculated width
+    min: Option<usize>,
+    max: Option<usize>,
+    fill: Option<String>,
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Clone, Debug, PartialEq)]
 enum ColWidthUnit {
     CHARACTER,
     FRACTIONAL,
+    AUTO,
+    PERCENT,
+}
+
+impl Default for Layout {
+    /// The default layout.
+    ///
+    /// Accept any number of columns, each one with a width
+    /// of 1 fractional unit and aligned to the left.
+    ///
+    /// Equivalent to the pattern `<-*`.
+    fn default() -> Self {
+        Self::new().fractional(1, crate::Alignment::LEFT).repeat()
+    }
 }
 
 impl Layout {
@@ -47,6 +105,9 @@ impl Layout {
     /// # Example
     ///
     /// ```
+    /// use textflow::Layout;
+    /// use textflow::Alignment::*;
+    ///
     /// let my_layout = Layout::new()
     ///     .fixed(4, RIGHT)
     ///     .fractional(1, CENTER)
@@ -60,19 +121,10 @@ impl Layout {
         }
     }
 
-    /// The default layout.
-    ///
-    /// Accept any number of columns, each one with a width
-    /// of 1 fractional unit and aligned to the left.
-    ///
-    /// Equivalent to the pattern `<-*`.
-    pub fn default() -> Self {
-        Self::new().fractional(1, crate::Alignment::LEFT).repeat()
-    }
-
     /// Creates a layout from a pattern.
     ///
-    /// Returns the successfully built layout or an error message.
+    /// Returns the successfully built layout, or an [Error] pointing
+    /// at the first invalid column.
     ///
     /// # Pattern Syntax
     ///
@@ -82,92 +134,69 @@ impl Layout {
     /// * An alignment :
     ///   * `<` for `LEFT`,
     ///   * `^` for `CENTER`,
-    ///   * `>` for `RIGHT` or
-    ///   * `=` for `JUSTIFY`
+    ///   * `>` for `RIGHT`,
+    ///   * `{` for `START`,
+    ///   * `}` for `END`,
+    ///   * `=` for `JUSTIFY`,
+    ///   * `.`, `:` or `,` for `ANCHOR` on that character or
+    ///   * `@` followed by any character for `ANCHOR` on this character
     ///
-    ///   (see [align()](crate::align::align) for more information).
+    ///   (see [align()](crate::align()) for more information).
     ///   If it is not specified, it will default to `LEFT`.
+    /// * A vertical alignment :
+    ///   * `'` for `TOP`,
+    ///   * `~` for `MIDDLE` or
+    ///   * `_` for `BOTTOM`
+    ///
+    ///   (see [Layout::vertical_align()] for more information).
+    ///   If it is not specified, it will default to `TOP`.
     /// * A width :
     ///   * either an integer meaning a fixed width in characters
     ///     (see [Layout::fixed()] for more information);
     ///   * or one or more hyphens `-` meaning a dynamic width in fractional
-    ///     units, with the number of hyphens being the width
-    ///     (see [Layout::fractional()] for more information).
+    ///     units, with the number of hyphens being the width, which can also
+    ///     be written as an integer followed by a hyphen (`3-` is `---`)
+    ///     (see [Layout::fractional()] for more information);
+    ///   * or an integer followed by `%` meaning a percentage of the
+    ///     total width (see [Layout::percent()] for more information);
+    ///   * or `a` meaning a width based on the content
+    ///     (see [Layout::auto()] for more information).
     ///
     ///   If it is not specified, it will default to 1 fractional unit.
+    /// * Constraints on the width : `[min..max]`, where both `min` and `max`
+    ///   are optional (see [Layout::min_width()] and [Layout::max_width()]
+    ///   for more information).
+    /// * A fill pattern between parentheses, like `(.)` or `(=)`
+    ///   (see [Layout::fill()] for more information).
     /// * A repeating flag: `*`, wich can only be used on one column
     ///   (see [Layout::repeat()] for more information).
     ///
+    /// A backslash `\` escapes the following character in a fill pattern
+    /// or after `@`, so that it can be a space, a parenthesis or a backslash.
+    ///
+    /// # Errors
+    ///
+    /// A column that can't be parsed gives [Error::InvalidColumn], with the
+    /// position of the unexpected character and what was expected there,
+    /// which [Error::diagnostic] can point at in the pattern.
+    ///
     /// # Example
     ///
     /// ```
-    /// Layout::from_pattern("^5 <-*").unwrap()
+    /// # use textflow::Layout;
+    /// Layout::from_pattern("^5 <-*").unwrap();
     /// ```
     /// can be read as “one column five characters wide followed by
     /// zero, one or more columns with equal width”
     /// and is equivalent to
     /// ```
+    /// # use textflow::{Alignment, Layout};
     /// Layout::new()
     ///     .fixed(5, Alignment::CENTER)
-    ///     .fractional(1, Alignment::LEFT).repeat()
+    ///     .fractional(1, Alignment::LEFT).repeat();
     /// ```
-    pub fn from_pattern(pattern: &str) -> Result<Self, String> {
-        lazy_static! {
-            static ref RE_COLUMN: Regex = Regex::new(r"^([<^>=]?)(-*|\d*)(\*?)$").unwrap();
-            static ref RE_HYPHENS: Regex = Regex::new(r"^-+$").unwrap();
-        }
-
-        let mut parsed = Self::new();
-        let mut already_repeated = false;
-
-        for column_pattern in pattern.split(" ") {
-            match RE_COLUMN.captures(column_pattern) {
-                None => {
-                    return Err(format!(
-                        "Invalid column [{}] in pattern [{}]",
-                        column_pattern, pattern
-                    ))
-                }
-
-                Some(groups) => {
-                    if groups.get(0).unwrap().as_str().is_empty() {
-                        // skip multiple spaces
-                        continue;
-                    }
-
-                    // alignment
-                    let align = match groups.get(1).unwrap().as_str() {
-                        "^" => crate::Alignment::CENTER,
-                        ">" => crate::Alignment::RIGHT,
-                        "=" => crate::Alignment::JUSTIFY,
-                        // default alignment is left
-                        &_ => crate::Alignment::LEFT,
-                    };
-
-                    // width
-                    let size = groups.get(2).unwrap().as_str();
-                    if size.is_empty() {
-                        // default column size is 1fr
-                        parsed = parsed.fractional(1, align);
-                    } else if RE_HYPHENS.is_match(size) {
-                        parsed = parsed.fractional(size.len(), align);
-                    } else {
-                        parsed = parsed.fixed(size.parse::<usize>().unwrap(), align);
-                    }
-
-                    // repeat
-                    if groups.get(3).unwrap().as_str() == "*" {
-                        if already_repeated {
-                            return Err(String::from("Only one column can be repeated"));
-                        } else {
-                            parsed = parsed.repeat();
-                            already_repeated = true;
-                        }
-                    }
-                }
-            }
-        }
-        return Ok(parsed);
+    pub fn from_pattern(pattern: &str) -> Result<Self, Error> {
+        return pattern::parse(pattern);
     }
 
     /// Add a column with fractional size.
@@ -176,37 +205,118 @@ impl Layout {
     /// this column divided by the sum of the widths
     /// of all the fractional columns (similar
     /// to the `fr` unit in css).
-    pub fn fractional(mut self, size: usize, alignment: Alignment) -> Self {
-        match self.fill {
-            None => self.left.push(DynCol {
-                unit: ColWidthUnit::FRACTIONAL,
-                size,
-                align: alignment,
-            }),
-            Some(_) => self.right.push(DynCol {
-                unit: ColWidthUnit::FRACTIONAL,
-                size,
-                align: alignment,
-            }),
-        }
-        return self;
+    pub fn fractional(self, size: usize, alignment: Alignment) -> Self {
+        self.push(DynCol {
+            unit: ColWidthUnit::FRACTIONAL,
+            size,
+            align: alignment,
+            ..Default::default()
+        })
     }
 
     /// Add a column with a fixed width in characters.
     ///
     /// The calculated width will be exactly the same.
-    pub fn fixed(mut self, size: usize, alignment: Alignment) -> Self {
+    pub fn fixed(self, size: usize, alignment: Alignment) -> Self {
+        self.push(DynCol {
+            unit: ColWidthUnit::CHARACTER,
+            size,
+            align: alignment,
+            ..Default::default()
+        })
+    }
+
+    /// Add a column with a width relative to the total width.
+    ///
+    /// The calculated width will be `size` percent of the width
+    /// given to the layout. If the percentages add up to more than
+    /// 100%, or if there isn't enough space left by the fixed columns,
+    /// they are shrunk proportionally. The fractional columns share
+    /// whatever is left.
+    pub fn percent(self, size: usize, alignment: Alignment) -> Self {
+        self.push(DynCol {
+            unit: ColWidthUnit::PERCENT,
+            size,
+            align: alignment,
+            ..Default::default()
+        })
+    }
+
+    /// Add a column with an automatic width.
+    ///
+    /// The calculated width will be the width of the content
+    /// of the column, if there is enough space (similar to the
+    /// `auto` width in css). See [Layout::resolve_with].
+    pub fn auto(self, alignment: Alignment) -> Self {
+        self.push(DynCol {
+            unit: ColWidthUnit::AUTO,
+            size: 0,
+            align: alignment,
+            ..Default::default()
+        })
+    }
+
+    /// Set a minimum width on the last column of the layout.
+    ///
+    /// The calculated width will never be smaller, even if
+    /// there isn't enough space (similar to `minmax()` in css).
+    /// If the minimum is greater than the maximum, the minimum wins.
+    ///
+    /// **This function will panic if the layout has no columns**.
+    pub fn min_width(mut self, min: usize) -> Self {
+        self.last_column().expect("No column to constrain").min = Some(min);
+        return self;
+    }
+
+    /// Set a maximum width on the last column of the layout.
+    ///
+    /// The calculated width will never be greater, even if
+    /// there is space left. The other fractional columns will
+    /// share the space instead.
+    ///
+    /// **This function will panic if the layout has no columns**.
+    pub fn max_width(mut self, max: usize) -> Self {
+        self.last_column().expect("No column to constrain").max = Some(max);
+        return self;
+    }
+
+    /// Set the vertical alignment of the last column of the layout.
+    ///
+    /// Columns are aligned to the top by default.
+    ///
+    /// **This function will panic if the layout has no columns**.
+    pub fn vertical_align(mut self, alignment: VerticalAlignment) -> Self {
+        self.last_column().expect("No column to align").valign = alignment;
+        return self;
+    }
+
+    /// Set the fill pattern of the last column of the layout.
+    ///
+    /// The pattern replaces the spaces added to align the lines of
+    /// this column, see [Options::fill](crate::Options::fill).
+    ///
+    /// **This function will panic if the layout has no columns**.
+    pub fn fill(mut self, pattern: &str) -> Self {
+        self.last_column().expect("No column to fill").fill = Some(String::from(pattern));
+        return self;
+    }
+
+    // the column that was added last
+    fn last_column(&mut self) -> Option<&mut DynCol> {
+        if !self.right.is_empty() {
+            self.right.last_mut()
+        } else if self.fill.is_some() {
+            self.fill.as_mut()
+        } else {
+            self.left.last_mut()
+        }
+    }
+
+    // add a column before or after the repeating one
+    fn push(mut self, column: DynCol) -> Self {
         match self.fill {
-            None => self.left.push(DynCol {
-                unit: ColWidthUnit::CHARACTER,
-                size,
-                align: alignment,
-            }),
-            Some(_) => self.right.push(DynCol {
-                unit: ColWidthUnit::CHARACTER,
-                size,
-                align: alignment,
-            }),
+            None => self.left.push(column),
+            Some(_) => self.right.push(column),
         }
         return self;
     }
@@ -220,28 +330,189 @@ impl Layout {
     ///
     /// Only one column can be repeated. **This function
     /// will panic if it is called on a layout that
-    /// already has a repeating column**.
-    /// A `try_repeat` function may be added in a future
-    /// version if needed.
-    pub fn repeat(mut self) -> Self {
-        match self.fill {
-            None => self.fill = Some(self.left.pop().expect("No column to repeat")),
-            Some(_) => panic!("Only one column can be repeated"),
+    /// already has a repeating column or that has no columns**.
+    /// Use [Layout::try_repeat] to handle these errors instead.
+    pub fn repeat(self) -> Self {
+        match self.try_repeat() {
+            Ok(repeated) => repeated,
+            Err(Error::EmptyLayout) => panic!("No column to repeat"),
+            Err(_) => panic!("Only one column can be repeated"),
         }
-        return self;
+    }
+
+    /// Set the last column of the layout as repeating, without panicking.
+    ///
+    /// This is the same as [Layout::repeat], but fails with
+    /// [Error::EmptyLayout] if the layout has no columns, or with
+    /// [Error::AlreadyRepeating] if it already has a repeating column.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use textflow::{Error, Layout};
+    /// use textflow::Alignment::*;
+    ///
+    /// let layout = Layout::new().fixed(4, LEFT).try_repeat().unwrap();
+    /// assert_eq!(
+    ///     layout.fixed(2, RIGHT).try_repeat(),
+    ///     Err(Error::AlreadyRepeating { index: 1 })
+    /// );
+    /// ```
+    pub fn try_repeat(mut self) -> Result<Self, Error> {
+        if self.fill.is_some() {
+            // the last column
+            return Err(Error::AlreadyRepeating {
+                index: self.column_count() - 1,
+            });
+        }
+        self.fill = Some(self.left.pop().ok_or(Error::EmptyLayout)?);
+        return Ok(self);
+    }
+
+    /// Check the layout for columns that would always be empty.
+    ///
+    /// Fails with [Error::EmptyLayout] if the layout has no columns, or with
+    /// [Error::ZeroWidthColumn] for the first fixed, fractional or percentage
+    /// column with a size of zero, or with a maximum width of zero (unless it
+    /// has a minimum width). The index is the position of the column in the
+    /// layout, the repeating column being counted once.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use textflow::{Error, Layout};
+    ///
+    /// assert_eq!(Layout::from_pattern("<5 >-*").unwrap().validate(), Ok(()));
+    /// assert_eq!(
+    ///     Layout::from_pattern("<5 >0").unwrap().validate(),
+    ///     Err(Error::ZeroWidthColumn { index: 1 })
+    /// );
+    /// assert_eq!(Layout::new().validate(), Err(Error::EmptyLayout));
+    /// ```
+    pub fn validate(&self) -> Result<(), Error> {
+        let columns = self
+            .left
+            .iter()
+            .chain(self.fill.iter())
+            .chain(self.right.iter());
+
+        let mut count = 0;
+        for (index, column) in columns.enumerate() {
+            if column.always_empty() {
+                return Err(Error::ZeroWidthColumn { index });
+            }
+            count += 1;
+        }
+
+        if count == 0 {
+            return Err(Error::EmptyLayout);
+        }
+        return Ok(());
+    }
+
+    // the alignment of each of `count` columns, before the
+    // number of columns is checked
+    pub(crate) fn alignments(&self, count: usize) -> Vec<Alignment> {
+        (0..count)
+            .map(|i| {
+                if i < self.left.len() {
+                    return self.left[i].align;
+                }
+                let Some(repeating) = &self.fill else {
+                    return Alignment::LEFT;
+                };
+                // the columns after the repeating one are counted from the end
+                let from_end = count - 1 - i;
+                if from_end < self.right.len() {
+                    return self.right[self.right.len() - 1 - from_end].align;
+                }
+                return repeating.align;
+            })
+            .collect()
+    }
+
+    // the number of columns, counting the repeating one once
+    pub(crate) fn column_count(&self) -> usize {
+        self.left.len() + self.fill.iter().count() + self.right.len()
     }
 
     /// Calculate all the dynamic columns for a given width
     /// and number of columns.
-    pub fn resolve(&self, width: usize, columns: usize) -> Result<Vec<Column>, String> {
+    ///
+    /// Since the content is unknown, the columns with an automatic
+    /// width are treated as fractional columns of size 1
+    /// (use [Layout::resolve_with] instead).
+    ///
+    /// Fails with [Error::TooFewColumns] or [Error::TooManyColumns]
+    /// if the layout can't have this number of columns.
+    pub fn resolve(&self, width: usize, columns: usize) -> Result<Vec<Column>, Error> {
+        self.resolve_columns(width, columns, None)
+    }
+
+    /// Calculate all the dynamic columns for a given width
+    /// and the width of the content of each column.
+    ///
+    /// `content_widths` has one entry per column, which is the width
+    /// its content would take without being wrapped. The columns with
+    /// an automatic width get exactly that width if there is enough space,
+    /// otherwise the widest ones are shrunk first, so that narrow columns
+    /// like labels are kept whole if possible. The fractional columns
+    /// then share whatever width is left.
+    ///
+    /// Fails with [Error::TooFewColumns] or [Error::TooManyColumns]
+    /// if the layout can't have this number of columns.
+    pub fn resolve_with(
+        &self,
+        width: usize,
+        content_widths: &[usize],
+    ) -> Result<Vec<Column>, Error> {
+        self.resolve_columns(width, content_widths.len(), Some(content_widths))
+    }
+
+    /// Calculate all the dynamic columns for a given width
+    /// and the content of each column.
+    ///
+    /// This is the same as [Layout::resolve_with], but the width of the
+    /// content (its widest line, or its lines once lined up for a column
+    /// aligned on an anchor) is measured with `measure`, like
+    /// [CjkWidth](crate::CjkWidth) or your own [WidthMeasure].
+    pub fn resolve_measured(
+        &self,
+        width: usize,
+        content: &[&str],
+        measure: &dyn WidthMeasure,
+    ) -> Result<Vec<Column>, Error> {
+        let content_widths: Vec<usize> = content
+            .iter()
+            .zip(self.alignments(content.len()))
+            .map(|(content, alignment)| {
+                let extents = content.lines().map(|line| match alignment {
+                    // lined up, the widest parts add up
+                    Alignment::ANCHOR(anchor) => anchor_extent(line, anchor, measure),
+                    _ => (crate::ansi::display_width(line, measure), 0),
+                });
+                let (before, after) = extents.fold((0, 0), |(before, after), (b, a)| {
+                    (before.max(b), after.max(a))
+                });
+                before + after
+            })
+            .collect();
+        self.resolve_with(width, &content_widths)
+    }
+
+    fn resolve_columns(
+        &self,
+        width: usize,
+        columns: usize,
+        content_widths: Option<&[usize]>,
+    ) -> Result<Vec<Column>, Error> {
         let missing = columns as i32 - self.left.len() as i32 - self.right.len() as i32;
 
         if missing < 0 {
-            return Err(format!(
-                "Expected at least {} columns, got {}",
-                self.left.len() + self.right.len(),
-                columns
-            ));
+            return Err(Error::TooFewColumns {
+                expected: self.left.len() + self.right.len(),
+                actual: columns,
+            });
         } else {
             let mut expanded = Vec::new();
 
@@ -251,11 +522,10 @@ impl Layout {
             match &self.fill {
                 None => {
                     if missing > 0 {
-                        return Err(format!(
-                            "Expected {} columns, got {}",
-                            self.left.len(),
-                            columns
-                        ));
+                        return Err(Error::TooManyColumns {
+                            expected: self.left.len(),
+                            actual: columns,
+                        });
                     }
                 }
                 Some(col) => {
@@ -268,46 +538,267 @@ impl Layout {
                 }
             }
 
-            let fractions: usize = expanded
-                .iter()
-                .filter(|col| col.unit == ColWidthUnit::FRACTIONAL)
-                .map(|col| col.size)
-                .sum();
-            let absolute: usize = expanded
+            // without content, automatic columns act like `-`
+            let fraction_size = |col: &DynCol| match col.unit {
+                ColWidthUnit::FRACTIONAL => col.size,
+                ColWidthUnit::AUTO if content_widths.is_none() => 1,
+                _ => 0,
+            };
+
+            // the width of each column, `None` while it's not known
+            let mut widths: Vec<Option<usize>> = expanded
                 .iter()
-                .filter(|col| col.unit == ColWidthUnit::CHARACTER)
-                .map(|col| col.size)
-                .sum();
-            let remaining = std::cmp::max(fractions as i32, width as i32 - absolute as i32)
-                .try_into()
-                .unwrap();
-            let mut resolved = Vec::new();
-
-            let mut fractional_columns = split_evenly(remaining, fractions);
-            for col in expanded {
-                match col.unit {
-                    ColWidthUnit::CHARACTER => resolved.push(Column {
-                        width: col.size,
-                        alignment: col.align,
-                    }),
-                    ColWidthUnit::FRACTIONAL => {
-                        let mut total_width = 0;
-                        for _ in 0..col.size {
-                            total_width += fractional_columns.remove(0);
-                        }
-                        resolved.push(Column {
-                            width: total_width,
-                            alignment: col.align,
-                        });
+                .map(|col| match col.unit {
+                    ColWidthUnit::CHARACTER => Some(col.clamp(col.size)),
+                    _ => None,
+                })
+                .collect();
+            let absolute: usize = widths.iter().flatten().sum();
+            let fractions: usize = expanded.iter().map(|col| fraction_size(col)).sum();
+
+            // percentages are based on the whole width, but they
+            // can't take more than 100% or the space left
+            let relative: Vec<usize> = (0..expanded.len())
+                .filter(|i| expanded[*i].unit == ColWidthUnit::PERCENT)
+                .collect();
+            if !relative.is_empty() {
+                let percentages: Vec<usize> = relative.iter().map(|i| expanded[*i].size).collect();
+                let total = std::cmp::min(percentages.iter().sum::<usize>(), 100);
+                // leave at least one character per fraction
+                let available = width.saturating_sub(absolute).saturating_sub(fractions);
+                let shares =
+                    split_weighted(std::cmp::min(width * total / 100, available), &percentages);
+                for (i, share) in relative.iter().zip(shares) {
+                    widths[*i] = Some(expanded[*i].clamp(share));
+                }
+            }
+            let absolute: usize = widths.iter().flatten().sum();
+
+            if let Some(content_widths) = content_widths {
+                let automatic: Vec<usize> = (0..expanded.len())
+                    .filter(|i| expanded[*i].unit == ColWidthUnit::AUTO)
+                    .collect();
+                // the preferred width of the automatic columns
+                let preferred: Vec<usize> = automatic
+                    .iter()
+                    .map(|i| expanded[*i].clamp(content_widths[*i]))
+                    .collect();
+                // leave at least one character per fraction
+                let available = width.saturating_sub(absolute).saturating_sub(fractions);
+                // when they don't fit, the widest ones give up space first,
+                // so that short labels are kept whole
+                let shrunk = shrink_widest(&preferred, available);
+                for (i, auto_width) in automatic.iter().zip(shrunk) {
+                    widths[*i] = Some(expanded[*i].clamp(auto_width));
+                }
+            }
+
+            // the fractional columns share what's left, and the ones that
+            // are out of bounds are set aside until all of them fit
+            let mut flexible: Vec<usize> = (0..expanded.len())
+                .filter(|i| widths[*i].is_none())
+                .collect();
+            while !flexible.is_empty() {
+                let used: usize = widths.iter().flatten().sum();
+                let sizes: Vec<usize> = flexible
+                    .iter()
+                    .map(|i| fraction_size(expanded[*i]))
+                    .collect();
+                let remaining = std::cmp::max(sizes.iter().sum(), width.saturating_sub(used));
+
+                let shares = split_fractions(remaining, &sizes);
+                let mut out_of_bounds = false;
+                for (i, share) in flexible.iter().zip(shares.iter()) {
+                    let clamped = expanded[*i].clamp(*share);
+                    if clamped != *share {
+                        widths[*i] = Some(clamped);
+                        out_of_bounds = true;
                     }
                 }
+
+                if out_of_bounds {
+                    flexible.retain(|i| widths[*i].is_none());
+                } else {
+                    for (i, share) in flexible.iter().zip(shares) {
+                        widths[*i] = Some(share);
+                    }
+                    flexible.clear();
+                }
             }
 
+            let resolved = expanded
+                .iter()
+                .zip(widths)
+                .map(|(col, width)| Column {
+                    width: width.unwrap_or(0),
+                    alignment: col.align,
+                    vertical_alignment: col.valign,
+                    fill: col.fill.clone(),
+                })
+                .collect();
+
             return Ok(resolved);
         }
     }
 }
 
+impl DynCol {
+    // applies the min/max constraints to a width
+    fn clamp(&self, width: usize) -> usize {
+        let mut clamped = width;
+        if let Some(max) = self.max {
+            clamped = std::cmp::min(clamped, max);
+        }
+        // the minimum wins over the maximum
+        if let Some(min) = self.min {
+            clamped = std::cmp::max(clamped, min);
+        }
+        return clamped;
+    }
+}
+
+impl FromStr for Layout {
+    type Err = Error;
+
+    /// Parses a pattern, see [Layout::from_pattern].
+    fn from_str(pattern: &str) -> Result<Self, Self::Err> {
+        Self::from_pattern(pattern)
+    }
+}
+
+impl fmt::Display for Layout {
+    /// Writes the pattern of the layout, see [Layout::from_pattern].
+    ///
+    /// Parsing this pattern gives back the same layout.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let columns = self.left.iter().map(|column| (column, false));
+        let columns = columns.chain(self.fill.iter().map(|column| (column, true)));
+        let columns = columns.chain(self.right.iter().map(|column| (column, false)));
+
+        for (i, (column, repeat)) in columns.enumerate() {
+            if i != 0 {
+                f.write_str(" ")?;
+            }
+            write!(f, "{}", column)?;
+            if repeat {
+                f.write_str("*")?;
+            }
+        }
+        return Ok(());
+    }
+}
+
+impl fmt::Display for DynCol {
+    // writes the column as in a pattern, without the repeating flag
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.align)?;
+
+        match self.valign {
+            VerticalAlignment::TOP => (),
+            VerticalAlignment::MIDDLE => f.write_str("~")?,
+            VerticalAlignment::BOTTOM => f.write_str("_")?,
+        }
+
+        match self.unit {
+            // hyphens can't say zero
+            ColWidthUnit::FRACTIONAL if self.size == 0 => f.write_str("0-")?,
+            ColWidthUnit::FRACTIONAL => f.write_str(&"-".repeat(self.size))?,
+            ColWidthUnit::CHARACTER => write!(f, "{}", self.size)?,
+            ColWidthUnit::PERCENT => write!(f, "{}%", self.size)?,
+            ColWidthUnit::AUTO => f.write_str("a")?,
+        }
+
+        if self.min.is_some() || self.max.is_some() {
+            let bound = |bound: Option<usize>| bound.map(|b| b.to_string()).unwrap_or_default();
+            write!(f, "[{}..{}]", bound(self.min), bound(self.max))?;
+        }
+
+        if let Some(fill) = &self.fill {
+            write!(f, "({})", escape(fill, &[' ', '(', ')']))?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl FromStr for Alignment {
+    type Err = Error;
+
+    /// Parses an alignment as written in a pattern, like `<` or `@;`
+    /// (see [Layout::from_pattern]), or its name, like `center`
+    /// (ignoring the case).
+    fn from_str(token: &str) -> Result<Self, Self::Err> {
+        let named = match token.to_ascii_lowercase().as_str() {
+            "left" => Some(Alignment::LEFT),
+            "center" => Some(Alignment::CENTER),
+            "right" => Some(Alignment::RIGHT),
+            "start" => Some(Alignment::START),
+            "end" => Some(Alignment::END),
+            "justify" => Some(Alignment::JUSTIFY),
+            _ => None,
+        };
+        named
+            .or_else(|| parse_alignment(token))
+            .ok_or_else(|| Error::InvalidAlignment {
+                token: String::from(token),
+            })
+    }
+}
+
+impl fmt::Display for Alignment {
+    /// Writes the alignment as in a pattern, like `<` or `@;`
+    /// (see [Layout::from_pattern]).
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Alignment::LEFT => f.write_str("<"),
+            Alignment::CENTER => f.write_str("^"),
+            Alignment::RIGHT => f.write_str(">"),
+            Alignment::START => f.write_str("{"),
+            Alignment::END => f.write_str("}"),
+            Alignment::JUSTIFY => f.write_str("="),
+            Alignment::ANCHOR(anchor @ ('.' | ':' | ',')) => write!(f, "{}", anchor),
+            Alignment::ANCHOR(anchor) => write!(f, "@{}", escape(&anchor.to_string(), &[' '])),
+        }
+    }
+}
+
+impl DynCol {
+    // whether the calculated width is always zero
+    fn always_empty(&self) -> bool {
+        if self.min.unwrap_or(0) > 0 {
+            return false;
+        }
+        if self.max == Some(0) {
+            return true;
+        }
+        return self.size == 0 && self.unit != ColWidthUnit::AUTO;
+    }
+}
+
+impl Default for DynCol {
+    // 1fr, aligned to the left and without constraints
+    fn default() -> Self {
+        Self {
+            size: 1,
+            unit: ColWidthUnit::FRACTIONAL,
+            align: Alignment::LEFT,
+            valign: VerticalAlignment::TOP,
+            min: None,
+            max: None,
+            fill: None,
+        }
+    }
+}
+
+// divides `number` between fractional columns of the given `sizes`
+fn split_fractions(number: usize, sizes: &[usize]) -> Vec<usize> {
+    let mut units = split_evenly(number, sizes.iter().sum()).into_iter();
+    sizes
+        .iter()
+        .map(|size| units.by_ref().take(*size).sum())
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -332,6 +823,7 @@ mod tests {
                 size: 1,
                 unit: ColWidthUnit::FRACTIONAL,
                 align: Alignment::LEFT,
+                ..Default::default()
             })
         );
         assert_eq!(default.right, vec![]);
@@ -348,11 +840,13 @@ mod tests {
                     size: 7,
                     unit: ColWidthUnit::CHARACTER,
                     align: Alignment::CENTER,
+                    ..Default::default()
                 },
                 DynCol {
                     size: 1,
                     unit: ColWidthUnit::FRACTIONAL,
                     align: Alignment::LEFT,
+                    ..Default::default()
                 }
             ]
         );
@@ -362,6 +856,7 @@ mod tests {
                 size: 3,
                 unit: ColWidthUnit::CHARACTER,
                 align: Alignment::LEFT,
+                ..Default::default()
             })
         );
         assert_eq!(
@@ -370,6 +865,7 @@ mod tests {
                 size: 1,
                 unit: ColWidthUnit::FRACTIONAL,
                 align: Alignment::JUSTIFY,
+                ..Default::default()
             }]
         );
 
@@ -382,16 +878,19 @@ mod tests {
                     size: 1,
                     unit: ColWidthUnit::FRACTIONAL,
                     align: Alignment::RIGHT,
+                    ..Default::default()
                 },
                 DynCol {
                     size: 2,
                     unit: ColWidthUnit::FRACTIONAL,
                     align: Alignment::LEFT,
+                    ..Default::default()
                 },
                 DynCol {
                     size: 3,
                     unit: ColWidthUnit::FRACTIONAL,
                     align: Alignment::JUSTIFY,
+                    ..Default::default()
                 }
             ]
         );
@@ -400,11 +899,38 @@ mod tests {
 
         let invalid1 = Layout::from_pattern("^7 +8 9");
 
-        assert!(invalid1.is_err());
+        assert_eq!(
+            invalid1.err(),
+            Some(Error::InvalidColumn {
+                token: String::from("+8"),
+                position: 3,
+                offset: 3,
+                expected: String::from("an alignment, a vertical alignment, a width, `[`, `(`, `*` or the end of the column"),
+            })
+        );
 
         let invalid2 = Layout::from_pattern("^7 8* 9* 10");
 
-        assert!(invalid2.is_err());
+        assert_eq!(
+            invalid2.err(),
+            Some(Error::DuplicateRepeat {
+                token: String::from("9*"),
+                position: 6
+            })
+        );
+
+        // positions count the extra spaces
+        let invalid3 = Layout::from_pattern(">-  <--  ?");
+
+        assert_eq!(
+            invalid3.err(),
+            Some(Error::InvalidColumn {
+                token: String::from("?"),
+                position: 9,
+                offset: 9,
+                expected: String::from("an alignment, a vertical alignment, a width, `[`, `(`, `*` or the end of the column"),
+            })
+        );
     }
 
     #[test]
@@ -417,6 +943,7 @@ mod tests {
                 size: 4,
                 unit: ColWidthUnit::FRACTIONAL,
                 align: Alignment::CENTER,
+                ..Default::default()
             }]
         );
         assert_eq!(mock.fill, None);
@@ -431,6 +958,7 @@ mod tests {
                 size: 4,
                 unit: ColWidthUnit::FRACTIONAL,
                 align: Alignment::CENTER,
+                ..Default::default()
             })
         );
         assert_eq!(
@@ -439,6 +967,7 @@ mod tests {
                 size: 9,
                 unit: ColWidthUnit::FRACTIONAL,
                 align: Alignment::RIGHT,
+                ..Default::default()
             }]
         );
     }
@@ -453,6 +982,7 @@ mod tests {
                 size: 4,
                 unit: ColWidthUnit::CHARACTER,
                 align: Alignment::CENTER,
+                ..Default::default()
             }]
         );
         assert_eq!(mock.fill, None);
@@ -467,6 +997,7 @@ mod tests {
                 size: 4,
                 unit: ColWidthUnit::CHARACTER,
                 align: Alignment::CENTER,
+                ..Default::default()
             })
         );
         assert_eq!(
@@ -475,10 +1006,353 @@ mod tests {
                 size: 9,
                 unit: ColWidthUnit::CHARACTER,
                 align: Alignment::RIGHT,
+                ..Default::default()
             }]
         );
     }
 
+    #[test]
+    fn test_percent() {
+        let mock = Layout::new()
+            .percent(30, Alignment::LEFT)
+            .percent(5, Alignment::RIGHT)
+            .repeat();
+
+        assert_eq!(
+            mock.left,
+            vec![DynCol {
+                size: 30,
+                unit: ColWidthUnit::PERCENT,
+                align: Alignment::LEFT,
+                ..Default::default()
+            }]
+        );
+        assert_eq!(
+            mock.fill,
+            Some(DynCol {
+                size: 5,
+                unit: ColWidthUnit::PERCENT,
+                align: Alignment::RIGHT,
+                ..Default::default()
+            })
+        );
+
+        let parsed = Layout::from_pattern("30% >5%*").unwrap();
+
+        assert_eq!(parsed.left, mock.left);
+        assert_eq!(parsed.fill, mock.fill);
+
+        assert!(Layout::from_pattern("%").is_err());
+        assert!(Layout::from_pattern("-%").is_err());
+    }
+
+    #[test]
+    fn test_auto() {
+        let mock = Layout::new()
+            .auto(Alignment::RIGHT)
+            .repeat()
+            .auto(Alignment::LEFT);
+
+        assert_eq!(mock.left, vec![]);
+        assert_eq!(
+            mock.fill,
+            Some(DynCol {
+                size: 0,
+                unit: ColWidthUnit::AUTO,
+                align: Alignment::RIGHT,
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            mock.right,
+            vec![DynCol {
+                size: 0,
+                unit: ColWidthUnit::AUTO,
+                align: Alignment::LEFT,
+                ..Default::default()
+            }]
+        );
+
+        let parsed = Layout::from_pattern(">a* a").unwrap();
+
+        assert_eq!(parsed.fill, mock.fill);
+        assert_eq!(parsed.right, mock.right);
+    }
+
+    #[test]
+    fn test_min_max_width() {
+        let mock = Layout::new()
+            .fractional(1, Alignment::LEFT)
+            .min_width(12)
+            .max_width(40)
+            .fixed(3, Alignment::RIGHT)
+            .repeat()
+            .max_width(2)
+            .auto(Alignment::CENTER)
+            .min_width(5);
+
+        assert_eq!(
+            mock.left,
+            vec![DynCol {
+                size: 1,
+                unit: ColWidthUnit::FRACTIONAL,
+                align: Alignment::LEFT,
+                valign: VerticalAlignment::TOP,
+                min: Some(12),
+                max: Some(40),
+                fill: None,
+            }]
+        );
+        assert_eq!(
+            mock.fill,
+            Some(DynCol {
+                size: 3,
+                unit: ColWidthUnit::CHARACTER,
+                align: Alignment::RIGHT,
+                valign: VerticalAlignment::TOP,
+                min: None,
+                max: Some(2),
+                fill: None,
+            })
+        );
+        assert_eq!(
+            mock.right,
+            vec![DynCol {
+                size: 0,
+                unit: ColWidthUnit::AUTO,
+                align: Alignment::CENTER,
+                valign: VerticalAlignment::TOP,
+                min: Some(5),
+                max: None,
+                fill: None,
+            }]
+        );
+
+        let parsed = Layout::from_pattern("<[12..40] >3[..2]* ^a[5..]").unwrap();
+
+        assert_eq!(parsed.left, mock.left);
+        assert_eq!(parsed.fill, mock.fill);
+        assert_eq!(parsed.right, mock.right);
+
+        assert!(Layout::from_pattern("-[12]").is_err());
+        assert!(Layout::from_pattern("-[a..b]").is_err());
+    }
+
+    #[test]
+    fn test_vertical_align() {
+        let mock = Layout::new()
+            .fixed(3, Alignment::LEFT)
+            .vertical_align(VerticalAlignment::BOTTOM)
+            .fractional(1, Alignment::RIGHT)
+            .vertical_align(VerticalAlignment::MIDDLE)
+            .repeat()
+            .fractional(1, Alignment::LEFT);
+
+        assert_eq!(
+            mock.left,
+            vec![DynCol {
+                size: 3,
+                unit: ColWidthUnit::CHARACTER,
+                valign: VerticalAlignment::BOTTOM,
+                ..Default::default()
+            }]
+        );
+        assert_eq!(
+            mock.fill,
+            Some(DynCol {
+                align: Alignment::RIGHT,
+                valign: VerticalAlignment::MIDDLE,
+                ..Default::default()
+            })
+        );
+        assert_eq!(mock.right, vec![DynCol::default()]);
+
+        let parsed = Layout::from_pattern("_3 >~-* '").unwrap();
+
+        assert_eq!(parsed.left, mock.left);
+        assert_eq!(parsed.fill, mock.fill);
+        assert_eq!(parsed.right, mock.right);
+
+        // the vertical alignment comes after the horizontal one
+        assert!(Layout::from_pattern("~>").is_err());
+
+        assert_eq!(
+            parsed.resolve(10, 2).unwrap()[0].vertical_alignment,
+            VerticalAlignment::BOTTOM
+        );
+    }
+
+    #[test]
+    fn test_fill() {
+        let mock = Layout::new()
+            .fractional(1, Alignment::LEFT)
+            .fill(".")
+            .auto(Alignment::CENTER)
+            .min_width(4)
+            .fill("=-")
+            .fixed(2, Alignment::RIGHT);
+
+        assert_eq!(
+            mock.left,
+            vec![
+                DynCol {
+                    fill: Some(String::from(".")),
+                    ..Default::default()
+                },
+                DynCol {
+                    size: 0,
+                    unit: ColWidthUnit::AUTO,
+                    align: Alignment::CENTER,
+                    min: Some(4),
+                    fill: Some(String::from("=-")),
+                    ..Default::default()
+                },
+                DynCol {
+                    size: 2,
+                    unit: ColWidthUnit::CHARACTER,
+                    align: Alignment::RIGHT,
+                    ..Default::default()
+                }
+            ]
+        );
+
+        let parsed = Layout::from_pattern("<-(.) ^a[4..](=-) >2").unwrap();
+
+        assert_eq!(parsed.left, mock.left);
+
+        // the pattern comes after the constraints
+        assert!(Layout::from_pattern("^(=)a").is_err());
+        assert!(Layout::from_pattern("^a(()").is_err());
+        // escaped characters
+        assert_eq!(
+            Layout::from_pattern("(.\\ ) (\\)\\\\) ()").unwrap(),
+            Layout::new()
+                .fractional(1, Alignment::LEFT)
+                .fill(". ")
+                .fractional(1, Alignment::LEFT)
+                .fill(")\\")
+                .fractional(1, Alignment::LEFT)
+                .fill("")
+        );
+
+        assert_eq!(
+            parsed.resolve(10, 3).unwrap()[1].fill,
+            Some(String::from("=-"))
+        );
+    }
+
+    #[test]
+    fn test_anchor() {
+        let parsed = Layout::from_pattern(".8 :a ,-[2..]").unwrap();
+
+        assert_eq!(
+            parsed.left,
+            vec![
+                DynCol {
+                    size: 8,
+                    unit: ColWidthUnit::CHARACTER,
+                    align: Alignment::ANCHOR('.'),
+                    ..Default::default()
+                },
+                DynCol {
+                    size: 0,
+                    unit: ColWidthUnit::AUTO,
+                    align: Alignment::ANCHOR(':'),
+                    ..Default::default()
+                },
+                DynCol {
+                    align: Alignment::ANCHOR(','),
+                    min: Some(2),
+                    ..Default::default()
+                }
+            ]
+        );
+
+        assert!(Layout::from_pattern(".<3").is_err());
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Layout::default().to_string(), "<-*");
+        assert_eq!(Layout::new().to_string(), "");
+        assert_eq!(
+            Layout::from_pattern("^5   >~a[..3]* _20%[1..] ---(-=)")
+                .unwrap()
+                .to_string(),
+            "^5 >~a[..3]* <_20%[1..] <---(-=)"
+        );
+
+        // everything can be written back
+        let layout = Layout::new()
+            .fractional(0, Alignment::START)
+            .min_width(2)
+            .fixed(0, Alignment::END)
+            .fractional(3, Alignment::ANCHOR(' '))
+            .fill("( )")
+            .repeat()
+            .auto(Alignment::ANCHOR('\\'))
+            .percent(50, Alignment::ANCHOR('@'));
+        let pattern = layout.to_string();
+        assert_eq!(pattern, "{0-[2..] }0 @\\ ---(\\(\\ \\))* @\\\\a @@50%");
+        assert_eq!(pattern.parse(), Ok(layout));
+    }
+
+    #[test]
+    fn test_from_str() {
+        let layout: Layout = "^5 <-*".parse().unwrap();
+        assert_eq!(layout, Layout::from_pattern("^5 <-*").unwrap());
+        assert_eq!(layout.clone(), layout);
+
+        assert_eq!(
+            "<- ?".parse::<Layout>(),
+            Err(Error::InvalidColumn {
+                token: String::from("?"),
+                position: 3,
+                offset: 3,
+                expected: String::from("an alignment, a vertical alignment, a width, `[`, `(`, `*` or the end of the column"),
+            })
+        );
+        // `N-` is the same as N hyphens
+        assert_eq!("3-".parse(), Layout::from_pattern("---"));
+    }
+
+    #[test]
+    fn test_alignment_str() {
+        let alignments = [
+            (Alignment::LEFT, "<"),
+            (Alignment::CENTER, "^"),
+            (Alignment::RIGHT, ">"),
+            (Alignment::START, "{"),
+            (Alignment::END, "}"),
+            (Alignment::JUSTIFY, "="),
+            (Alignment::ANCHOR('.'), "."),
+            (Alignment::ANCHOR(';'), "@;"),
+            (Alignment::ANCHOR(' '), "@\\ "),
+        ];
+        for (alignment, token) in alignments {
+            assert_eq!(alignment.to_string(), token);
+            assert_eq!(token.parse(), Ok(alignment));
+        }
+        assert_eq!("@.".parse(), Ok(Alignment::ANCHOR('.')));
+        assert_eq!("Center".parse(), Ok(Alignment::CENTER));
+        assert_eq!("justify".parse(), Ok(Alignment::JUSTIFY));
+
+        assert_eq!(
+            "<<".parse::<Alignment>(),
+            Err(Error::InvalidAlignment {
+                token: String::from("<<")
+            })
+        );
+        assert!("".parse::<Alignment>().is_err());
+        assert!("@".parse::<Alignment>().is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "No column to constrain")]
+    fn test_min_width_fail() {
+        let _invalid = Layout::new().min_width(3);
+    }
+
     #[test]
     fn test_repeat_pass() {
         let valid = Layout::new().fixed(4, Alignment::CENTER).repeat();
@@ -490,10 +1364,84 @@ mod tests {
                 size: 4,
                 unit: ColWidthUnit::CHARACTER,
                 align: Alignment::CENTER,
+                ..Default::default()
             })
         );
         assert_eq!(valid.right, vec![]);
     }
+    #[test]
+    #[should_panic(expected = "No column to repeat")]
+    fn test_repeat_empty() {
+        let _invalid = Layout::new().repeat();
+    }
+
+    #[test]
+    fn test_try_repeat() {
+        let layout = Layout::new().fixed(4, Alignment::CENTER).try_repeat();
+        assert_eq!(layout, Ok(Layout::from_pattern("^4*").unwrap()));
+
+        // the repeating column is the last one
+        assert_eq!(
+            layout.clone().unwrap().try_repeat(),
+            Err(Error::AlreadyRepeating { index: 0 })
+        );
+        assert_eq!(
+            layout
+                .unwrap()
+                .fractional(1, Alignment::LEFT)
+                .fixed(2, Alignment::LEFT)
+                .try_repeat(),
+            Err(Error::AlreadyRepeating { index: 2 })
+        );
+
+        assert_eq!(Layout::new().try_repeat(), Err(Error::EmptyLayout));
+    }
+
+    #[test]
+    fn test_alignments() {
+        let layout = Layout::from_pattern("< ^* > .").unwrap();
+        assert_eq!(
+            layout.alignments(5),
+            vec![
+                Alignment::LEFT,
+                Alignment::CENTER,
+                Alignment::CENTER,
+                Alignment::RIGHT,
+                Alignment::ANCHOR('.')
+            ]
+        );
+        // even if there are not enough columns
+        assert_eq!(layout.alignments(1), vec![Alignment::LEFT]);
+        assert_eq!(Layout::new().alignments(1), vec![Alignment::LEFT]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(Layout::default().validate(), Ok(()));
+        assert_eq!(
+            Layout::from_pattern("a 0[1..] -").unwrap().validate(),
+            Ok(())
+        );
+
+        assert_eq!(Layout::new().validate(), Err(Error::EmptyLayout));
+        assert_eq!(
+            Layout::from_pattern("<5 >0").unwrap().validate(),
+            Err(Error::ZeroWidthColumn { index: 1 })
+        );
+        assert_eq!(
+            Layout::from_pattern("- 0-* 3").unwrap().validate(),
+            Err(Error::ZeroWidthColumn { index: 1 })
+        );
+        assert_eq!(
+            Layout::from_pattern("- 2 0%").unwrap().validate(),
+            Err(Error::ZeroWidthColumn { index: 2 })
+        );
+        assert_eq!(
+            Layout::from_pattern("-[..0]").unwrap().validate(),
+            Err(Error::ZeroWidthColumn { index: 0 })
+        );
+    }
+
     #[test]
     #[should_panic(expected = "Only one column can be repeated")]
     fn test_repeat_fail() {
@@ -514,19 +1462,23 @@ mod tests {
             Ok(vec![
                 Column {
                     width: 5,
-                    alignment: Alignment::RIGHT
+                    alignment: Alignment::RIGHT,
+                    ..Default::default()
                 },
                 Column {
                     width: 6,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 },
                 Column {
                     width: 6,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 },
                 Column {
                     width: 6,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 }
             ])
         );
@@ -536,20 +1488,29 @@ mod tests {
             Ok(vec![
                 Column {
                     width: 5,
-                    alignment: Alignment::RIGHT
+                    alignment: Alignment::RIGHT,
+                    ..Default::default()
                 },
                 Column {
                     width: 1,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 },
                 Column {
                     width: 1,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 },
             ])
         );
         // not enough columns
-        assert!(generic.resolve(23, 0).is_err());
+        assert_eq!(
+            generic.resolve(23, 0),
+            Err(Error::TooFewColumns {
+                expected: 1,
+                actual: 0
+            })
+        );
 
         let fixed_only = Layout::from_pattern(">5 10* 15").unwrap();
 
@@ -559,15 +1520,18 @@ mod tests {
             Ok(vec![
                 Column {
                     width: 5,
-                    alignment: Alignment::RIGHT
+                    alignment: Alignment::RIGHT,
+                    ..Default::default()
                 },
                 Column {
                     width: 10,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 },
                 Column {
                     width: 15,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 }
             ])
         );
@@ -576,15 +1540,18 @@ mod tests {
             Ok(vec![
                 Column {
                     width: 5,
-                    alignment: Alignment::RIGHT
+                    alignment: Alignment::RIGHT,
+                    ..Default::default()
                 },
                 Column {
                     width: 10,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 },
                 Column {
                     width: 15,
-                    alignment: Alignment::LEFT
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
                 }
             ])
         );
@@ -592,8 +1559,167 @@ mod tests {
         let no_repeat = Layout::from_pattern("11 -- 22").unwrap();
 
         // not enough columns
-        assert!(no_repeat.resolve(99, 2).is_err());
+        assert_eq!(
+            no_repeat.resolve(99, 2),
+            Err(Error::TooFewColumns {
+                expected: 3,
+                actual: 2
+            })
+        );
         // too many columns
-        assert!(no_repeat.resolve(99, 5).is_err());
+        assert_eq!(
+            no_repeat.resolve(99, 5),
+            Err(Error::TooManyColumns {
+                expected: 3,
+                actual: 5
+            })
+        );
+    }
+
+    #[test]
+    fn test_resolve_with() {
+        let layout = Layout::from_pattern(">a <- 3 a").unwrap();
+
+        // enough space
+        assert_eq!(
+            layout.resolve_with(30, &[5, 100, 2, 8]),
+            Ok(vec![
+                Column {
+                    width: 5,
+                    alignment: Alignment::RIGHT,
+                    ..Default::default()
+                },
+                Column {
+                    width: 14,
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
+                },
+                Column {
+                    width: 3,
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
+                },
+                Column {
+                    width: 8,
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
+                }
+            ])
+        );
+        // the widest automatic column is shrunk first, leaving
+        // one character to the fractional one
+        assert_eq!(
+            layout.resolve_with(14, &[5, 100, 2, 15]),
+            Ok(vec![
+                Column {
+                    width: 5,
+                    alignment: Alignment::RIGHT,
+                    ..Default::default()
+                },
+                Column {
+                    width: 1,
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
+                },
+                Column {
+                    width: 3,
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
+                },
+                Column {
+                    width: 5,
+                    alignment: Alignment::LEFT,
+                    ..Default::default()
+                }
+            ])
+        );
+        // same errors as `resolve`
+        assert_eq!(
+            layout.resolve_with(30, &[1, 2]),
+            Err(Error::TooFewColumns {
+                expected: 4,
+                actual: 2
+            })
+        );
+        // without content, automatic columns are fractional
+        assert_eq!(
+            layout.resolve(9, 4),
+            Layout::from_pattern(">- <- 3 -").unwrap().resolve(9, 4)
+        );
+    }
+
+    // the width of each resolved column
+    fn widths(resolved: Result<Vec<Column>, Error>) -> Vec<usize> {
+        resolved
+            .unwrap()
+            .iter()
+            .map(|column| column.width)
+            .collect()
+    }
+
+    // resolves a pattern and keeps only the widths
+    fn resolve(pattern: &str, width: usize, columns: usize) -> Vec<usize> {
+        widths(
+            Layout::from_pattern(pattern)
+                .unwrap()
+                .resolve(width, columns),
+        )
+    }
+
+    #[test]
+    fn test_resolve_measured() {
+        let layout = Layout::from_pattern("a -").unwrap();
+        let content = ["±±\n±", "text"];
+
+        assert_eq!(
+            widths(layout.resolve_measured(10, &content, &crate::UnicodeWidth)),
+            vec![2, 8]
+        );
+        assert_eq!(
+            widths(layout.resolve_measured(10, &content, &crate::CjkWidth)),
+            vec![4, 6]
+        );
+
+        // the lines of an anchor column are lined up
+        let layout = Layout::from_pattern(".a -").unwrap();
+        assert_eq!(
+            widths(layout.resolve_measured(20, &["1.5\n12345\n2.25", "x"], &crate::UnicodeWidth)),
+            vec![8, 12]
+        );
+    }
+
+    #[test]
+    fn test_resolve_constraints() {
+        // the space left by a column that reached its maximum goes to the others
+        assert_eq!(resolve("-[12..40] -", 100, 2), vec![40, 60]);
+        // columns that reached their minimum take space from the others
+        assert_eq!(resolve("-[12..] -*", 20, 3), vec![12, 4, 4]);
+        // even if there isn't enough space
+        assert_eq!(resolve(">5 -[2..]*", 3, 3), vec![5, 2, 2]);
+        // fixed columns are constrained too
+        assert_eq!(resolve("10[..4] -", 10, 2), vec![4, 6]);
+        // the minimum wins
+        assert_eq!(resolve("-[8..5]", 10, 1), vec![8]);
+
+        // automatic columns
+        let layout = Layout::from_pattern("a[3..6] -").unwrap();
+        assert_eq!(widths(layout.resolve_with(20, &[10, 5])), vec![6, 14]);
+        assert_eq!(widths(layout.resolve_with(20, &[1, 5])), vec![3, 17]);
+    }
+
+    #[test]
+    fn test_resolve_percent() {
+        // the fractional columns share what's left
+        assert_eq!(resolve("30% - --", 100, 3), vec![30, 23, 47]);
+        // the fixed columns are not taken into account
+        assert_eq!(resolve("10 50% -", 60, 3), vec![10, 30, 20]);
+        // the remaining space is left empty if there are no fractional columns
+        assert_eq!(resolve("25% 25%", 80, 2), vec![20, 20]);
+        // more than 100%
+        assert_eq!(resolve("100% 50% -", 31, 3), vec![20, 10, 1]);
+        // not enough space next to the fixed columns
+        assert_eq!(resolve("20 50% 50%", 30, 3), vec![20, 5, 5]);
+        // percentages can be constrained
+        assert_eq!(resolve("50%[..10] -", 40, 2), vec![10, 30]);
     }
 }
@@ -1,42 +1,89 @@
 #![deny(missing_docs)]
+// the explicit `return`s and the all-caps enum variants are a deliberate style
+#![allow(clippy::needless_return, clippy::upper_case_acronyms)]
 
 //! A library built upon [`textwrap`](https://crates.io/crates/textwrap)
-//! that lets you print text in columns, aligned to the right, centered
-//! or justified.
+//! that lets you print text in columns or tables, aligned to the right,
+//! centered or justified.
 
-extern crate lazy_static;
-extern crate regex;
+#[cfg(feature = "serde")]
+extern crate serde;
 extern crate textwrap;
+extern crate unicode_segmentation;
 extern crate unicode_width;
 
-/// (re-exported from `textwrap`) Holds settings for wrapping and filling text.
-pub use textwrap::Options;
-
+mod ansi;
 mod utils;
 
 mod align;
 mod columns;
+#[cfg(feature = "serde")]
+mod config;
+mod error;
+mod flow;
+mod gutters;
 mod layout;
+mod lines;
+mod measure;
+mod options;
+mod pattern;
+mod table;
 
-pub use align::align;
-pub use columns::columns;
+pub use align::{align, align_lines, try_align};
+pub use columns::{columns, columns_lines, try_columns};
+pub use error::Error;
+pub use flow::{flow_columns, try_flow_columns};
+pub use gutters::Gutters;
 pub use layout::Layout;
+pub use lines::Lines;
+pub use measure::{CjkWidth, UnicodeWidth, WidthMeasure};
+pub use options::{Direction, Justification, LastLine, Options, Overflow, Tabs};
+pub use table::{table, try_table};
 
 /// Text alignment. See [align()] for details.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
 pub enum Alignment {
     /// Left-aligned
+    #[default]
     LEFT,
     /// Centered
     CENTER,
     /// Right-aligned
     RIGHT,
+    /// Aligned to the start of the text: to the left,
+    /// or to the right if the text is right-to-left
+    START,
+    /// Aligned to the end of the text: to the right,
+    /// or to the left if the text is right-to-left
+    END,
     /// Justified
     JUSTIFY,
+    /// Lined up on the first occurrence of a character,
+    /// like a decimal separator
+    ANCHOR(char),
+}
+
+/// Vertical alignment of a column. See [columns()] for details.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum VerticalAlignment {
+    /// Aligned to the top
+    #[default]
+    TOP,
+    /// Centered vertically
+    MIDDLE,
+    /// Aligned to the bottom
+    BOTTOM,
 }
 
 /// Column spacing. See [columns()] for details.
-#[derive(Copy, Clone, Debug)]
+///
+/// It can be written and parsed as `none`, `between` or `around`.
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub enum Spacing {
     /// No spacing
     NONE,
@@ -0,0 +1,338 @@
+#[cfg(test)]
+use std::borrow::Cow;
+use std::fmt;
+use std::io;
+
+use crate::align::{anchor_extent, anchor_line, LineSettings};
+use crate::ansi;
+use crate::columns::join_line;
+use crate::utils::WrappedLines;
+
+use crate::layout::Column;
+use crate::Alignment;
+use crate::Error;
+use crate::Gutters;
+#[cfg(test)]
+use crate::Overflow;
+use crate::VerticalAlignment;
+use crate::WidthMeasure;
+
+/// An iterator over the lines of aligned text or columns.
+///
+/// Each line is aligned when it is requested, so the output is never held
+/// in memory all at once. The lines don't end with a line feed.
+///
+/// It is returned by [align_lines()](crate::align_lines()) and
+/// [columns_lines()](crate::columns_lines()). A line that can't be
+/// printed gives an [Error] and ends the iteration.
+///
+/// # Examples
+///
+/// ```
+/// use textflow::align_lines;
+/// use textflow::Alignment::*;
+///
+/// for line in align_lines("a small extension for textwrap.", RIGHT, 20) {
+///     println!("> {}", line.unwrap());
+/// }
+/// ```
+pub struct Lines<'a> {
+    // the wrapped content of each column,
+    // with the lines that end a paragraph
+    content: Vec<WrappedLines<'a>>,
+    columns: Vec<Column>,
+    // the number of blank lines above the content of each column
+    offsets: Vec<usize>,
+    // the widest parts before and after the anchor in each column
+    extents: Vec<(usize, usize)>,
+    gutters: Gutters,
+    settings: LineSettings<'a>,
+    // style carried over from the previous lines of each column
+    styles: Vec<String>,
+    row: usize,
+    height: usize,
+    // an error that happened before the alignment
+    error: Option<Error>,
+}
+
+impl<'a> Lines<'a> {
+    pub(crate) fn new(
+        content: Vec<WrappedLines<'a>>,
+        columns: Vec<Column>,
+        gutters: Gutters,
+        settings: LineSettings<'a>,
+    ) -> Self {
+        let height = content.iter().map(|lines| lines.len()).max().unwrap_or(0);
+
+        // align the columns vertically
+        let offsets = content
+            .iter()
+            .zip(columns.iter())
+            .map(|(lines, column)| match column.vertical_alignment {
+                VerticalAlignment::TOP => 0,
+                VerticalAlignment::MIDDLE => (height - lines.len()) / 2,
+                VerticalAlignment::BOTTOM => height - lines.len(),
+            })
+            .collect();
+
+        let extents = content
+            .iter()
+            .zip(columns.iter())
+            .map(|(lines, column)| {
+                anchor_extents(
+                    lines.iter().map(|(line, _)| line.as_ref()),
+                    column,
+                    settings.measure,
+                )
+            })
+            .collect();
+
+        let styles = vec![String::new(); columns.len()];
+
+        Self {
+            content,
+            columns,
+            offsets,
+            extents,
+            gutters,
+            settings,
+            styles,
+            row: 0,
+            height,
+            error: None,
+        }
+    }
+
+    // gives only an error
+    pub(crate) fn failed(error: Error) -> Self {
+        let mut lines = Self::new(Vec::new(), Vec::new(), Gutters::new(), Default::default());
+        lines.error = Some(error);
+        return lines;
+    }
+
+    // lines up the anchors on other lines than those of this block,
+    // like the other rows of a table
+    pub(crate) fn share_anchors(mut self, extents: &[(usize, usize)]) -> Self {
+        self.extents = extents.to_vec();
+        return self;
+    }
+
+    /// Writes the remaining lines into a [fmt::Write], like a `String`.
+    ///
+    /// Every line is followed by a line feed, including the last one.
+    pub fn write_to<W: fmt::Write>(self, sink: &mut W) -> Result<(), Error> {
+        for line in self {
+            writeln!(sink, "{}", line?)?;
+        }
+        return Ok(());
+    }
+
+    /// Writes the remaining lines into an [io::Write], like a file
+    /// or the standard output.
+    ///
+    /// Every line is followed by a line feed, including the last one.
+    pub fn write_io<W: io::Write>(self, sink: &mut W) -> Result<(), Error> {
+        for line in self {
+            writeln!(sink, "{}", line?)?;
+        }
+        return Ok(());
+    }
+
+    // aligns the next line of each column, without joining them
+    pub(crate) fn next_cells(&mut self) -> Option<Result<Vec<String>, Error>> {
+        if let Some(error) = self.error.take() {
+            return Some(Err(error));
+        }
+        if self.row >= self.height {
+            return None;
+        }
+        let row = self.row;
+        self.row += 1;
+
+        let mut cells = Vec::new();
+        for (i, column) in self.columns.iter().enumerate() {
+            // the index of the line in its column, which
+            // starts lower with the vertical alignment
+            let index = row.saturating_sub(self.offsets[i]);
+            // blank above and below the content
+            let (content, last_line) = row
+                .checked_sub(self.offsets[i])
+                .and_then(|row| self.content[i].get(row))
+                .map(|(line, end)| (line.as_ref(), *end))
+                .unwrap_or(("", true));
+
+            let styled = ansi::reopen_style(content, &self.styles[i]);
+            self.styles[i] = ansi::active_style(content, &self.styles[i]);
+
+            let (styled, alignment) =
+                place_anchor(styled, column, self.extents[i], self.settings.measure);
+
+            match crate::align::align_line(
+                &styled,
+                column.width,
+                alignment,
+                last_line,
+                index,
+                self.settings.for_column(column),
+            ) {
+                Ok(cell) => cells.push(cell),
+                Err(error) => {
+                    // stop there
+                    self.row = self.height;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        return Some(Ok(cells));
+    }
+}
+
+// the widest parts before and after the anchor in some lines of a column
+pub fn anchor_extents<'l>(
+    lines: impl Iterator<Item = &'l str>,
+    column: &Column,
+    measure: &dyn WidthMeasure,
+) -> (usize, usize) {
+    let Alignment::ANCHOR(anchor) = column.alignment else {
+        return (0, 0);
+    };
+    return lines
+        .filter(|line| !line.is_empty())
+        .map(|line| anchor_extent(line, anchor, measure))
+        .fold((0, 0), |(before, after), (line_before, line_after)| {
+            (before.max(line_before), after.max(line_after))
+        });
+}
+
+// puts the anchor of a line at the same place as on the other lines
+// of its column, as far right as possible, and tells how to align the result
+pub fn place_anchor(
+    line: String,
+    column: &Column,
+    extent: (usize, usize),
+    measure: &dyn WidthMeasure,
+) -> (String, Alignment) {
+    match column.alignment {
+        // the lines can't be lined up in the width of the column,
+        // they are aligned to the right rather than cut
+        Alignment::ANCHOR(_) if extent.0 + extent.1 > column.width => (line, Alignment::RIGHT),
+        Alignment::ANCHOR(anchor) if !line.is_empty() => {
+            let (before, after) = extent;
+            let before = before + column.width.saturating_sub(before + after);
+            (anchor_line(&line, anchor, before, measure), Alignment::LEFT)
+        }
+        alignment => (line, alignment),
+    }
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = Result<String, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let cells = self.next_cells()?;
+        return Some(cells.map(|cells| join_line(&cells, &self.gutters)));
+    }
+}
+
+#[test]
+fn test_lines() {
+    let column = Column {
+        width: 5,
+        ..Default::default()
+    };
+    let content = vec![vec![(Cow::from("one"), false), (Cow::from("two"), true)]];
+
+    let mut lines = Lines::new(
+        content,
+        vec![column],
+        Gutters::new().left("|"),
+        Overflow::SPILL.into(),
+    );
+
+    assert_eq!(lines.next(), Some(Ok(String::from("|one  "))));
+    assert_eq!(lines.next(), Some(Ok(String::from("|two  "))));
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_lines_error() {
+    let column = Column {
+        width: 2,
+        ..Default::default()
+    };
+    let content = vec![vec![(Cow::from("one"), false), (Cow::from("two"), true)]];
+
+    let mut lines = Lines::new(
+        content,
+        vec![column],
+        Gutters::new(),
+        Overflow::ERROR.into(),
+    );
+
+    assert_eq!(
+        lines.next(),
+        Some(Err(Error::LineTooWide {
+            width: 2,
+            actual: 3
+        }))
+    );
+    assert_eq!(lines.next(), None);
+}
+
+#[test]
+fn test_lines_anchor() {
+    let column = Column {
+        width: 8,
+        alignment: Alignment::ANCHOR('.'),
+        ..Default::default()
+    };
+    let content = vec![vec![
+        (Cow::from("3.14"), false),
+        (Cow::from("42"), false),
+        (Cow::from("-0.5"), false),
+        (Cow::from("100.125"), true),
+    ]];
+
+    let lines: Vec<String> = Lines::new(
+        content,
+        vec![column],
+        Gutters::new(),
+        Overflow::SPILL.into(),
+    )
+    .map(|line| line.unwrap())
+    .collect();
+
+    assert_eq!(lines, vec!["   3.14 ", "  42    ", "  -0.5  ", " 100.125"]);
+}
+
+#[test]
+fn test_write() {
+    let column = Column {
+        width: 3,
+        ..Default::default()
+    };
+    let content = vec![vec![(Cow::from("one"), false), (Cow::from("two"), true)]];
+
+    let mut string = String::new();
+    Lines::new(
+        content.clone(),
+        vec![column.clone()],
+        Gutters::new(),
+        Overflow::SPILL.into(),
+    )
+    .write_to(&mut string)
+    .unwrap();
+    assert_eq!(string, "one\ntwo\n");
+
+    let mut bytes = Vec::new();
+    Lines::new(
+        content,
+        vec![column],
+        Gutters::new(),
+        Overflow::SPILL.into(),
+    )
+    .write_io(&mut bytes)
+    .unwrap();
+    assert_eq!(bytes, b"one\ntwo\n");
+}
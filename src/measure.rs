@@ -0,0 +1,115 @@
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar as UniW;
+
+/// Measures the width of the text, in columns.
+///
+/// The text is measured one extended grapheme cluster (what is
+/// displayed as a single character) at a time, escape sequences
+/// being skipped beforehand. Implement this trait to change how
+/// wide some characters are, for example to match the emoji table
+/// of a specific terminal or to approximate a proportional font.
+///
+/// The measure is used to wrap, align, pad and truncate the lines and
+/// to size the columns with an automatic width. Only the wrapping
+/// algorithms of `textwrap` ([FirstFit](textwrap::wrap_algorithms::FirstFit)
+/// and [OptimalFit](textwrap::wrap_algorithms::OptimalFit)) use it,
+/// other algorithms see the words as measured by `textwrap`.
+///
+/// # Examples
+///
+/// ```
+/// use textflow::{align, Options, WidthMeasure};
+/// use textflow::Alignment::*;
+///
+/// // every character is twice as wide
+/// struct Double;
+///
+/// impl WidthMeasure for Double {
+///     fn grapheme_width(&self, grapheme: &str) -> usize {
+///         2 * grapheme.chars().count().min(1)
+///     }
+/// }
+///
+/// let options = Options::new(10).measure(&Double);
+/// assert_eq!(align("abc", RIGHT, options), "    abc");
+/// ```
+pub trait WidthMeasure {
+    /// The number of columns taken by an extended grapheme cluster.
+    fn grapheme_width(&self, grapheme: &str) -> usize;
+
+    /// The number of columns taken by a string without escape sequences.
+    fn width(&self, text: &str) -> usize {
+        text.graphemes(true)
+            .map(|grapheme| self.grapheme_width(grapheme))
+            .sum()
+    }
+}
+
+/// The width of the characters according to the Unicode standard,
+/// as most terminals display them (this is the default).
+///
+/// The characters of ambiguous width, like `±`, `→` or `…`,
+/// are one column wide.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct UnicodeWidth;
+
+/// The width of the characters in an East Asian context.
+///
+/// This is the same as [UnicodeWidth], except that the characters of
+/// ambiguous width are two columns wide, like in most CJK fonts.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct CjkWidth;
+
+impl WidthMeasure for UnicodeWidth {
+    fn grapheme_width(&self, grapheme: &str) -> usize {
+        cluster_width(grapheme, |ch| UniW::width(ch).unwrap_or(0))
+    }
+}
+
+impl WidthMeasure for CjkWidth {
+    fn grapheme_width(&self, grapheme: &str) -> usize {
+        cluster_width(grapheme, |ch| UniW::width_cjk(ch).unwrap_or(0))
+    }
+}
+
+// the width of a cluster, given the width of each character
+fn cluster_width(grapheme: &str, char_width: impl Fn(char) -> usize) -> usize {
+    // emoji presentation selector
+    if grapheme.contains('\u{fe0f}') {
+        return 2;
+    }
+    // flags are made of two regional indicators
+    if grapheme.chars().count() > 1
+        && grapheme
+            .chars()
+            .all(|ch| ('\u{1f1e6}'..='\u{1f1ff}').contains(&ch))
+    {
+        return 2;
+    }
+    // the modifiers and joined characters are
+    // displayed over or next to the first one
+    return grapheme.chars().map(char_width).max().unwrap_or(0);
+}
+
+#[test]
+fn test_unicode_width() {
+    assert_eq!(UnicodeWidth.width("error"), 5);
+    assert_eq!(UnicodeWidth.width("你好"), 4);
+    assert_eq!(UnicodeWidth.width("±→"), 2);
+    // grapheme clusters
+    assert_eq!(UnicodeWidth.width("e\u{301}"), 1);
+    assert_eq!(UnicodeWidth.width("👍🏽"), 2);
+    assert_eq!(UnicodeWidth.width("👨\u{200d}👩\u{200d}👧"), 2);
+    assert_eq!(UnicodeWidth.width("🇫🇷"), 2);
+    assert_eq!(UnicodeWidth.width("❤\u{fe0f}"), 2);
+}
+
+#[test]
+fn test_cjk_width() {
+    assert_eq!(CjkWidth.width("error"), 5);
+    assert_eq!(CjkWidth.width("你好"), 4);
+    // ambiguous characters
+    assert_eq!(CjkWidth.width("±→"), 4);
+    assert_eq!(CjkWidth.width("e\u{301}"), 1);
+    assert_eq!(CjkWidth.width("🇫🇷"), 2);
+}
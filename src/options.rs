@@ -0,0 +1,503 @@
+use std::fmt;
+
+use textwrap::word_separators::{UnicodeBreakProperties, WordSeparator};
+use textwrap::word_splitters::{HyphenSplitter, WordSplitter};
+use textwrap::wrap_algorithms::{OptimalFit, WrapAlgorithm};
+
+use crate::align::LineSettings;
+use crate::Alignment;
+use crate::UnicodeWidth;
+use crate::WidthMeasure;
+
+/// Holds settings for wrapping, filling and aligning text.
+///
+/// It extends [textwrap::Options] with settings specific to this crate.
+/// Everywhere an `Options` is expected, you can also use an integer
+/// (the width) or a [textwrap::Options].
+///
+/// # Examples
+///
+/// ```
+/// use textflow::{align, Options, Overflow};
+/// use textflow::Alignment::*;
+///
+/// let options = Options::new(8)
+///     .break_words(false)
+///     .overflow(Overflow::ELLIPSIS);
+///
+/// assert_eq!(align("the incomprehensibilities", LEFT, options), "the     \nincompr…");
+/// ```
+///
+/// # Migrating from 0.2
+///
+/// Up to version 0.2, `textflow::Options` was a re-export of
+/// [textwrap::Options]. The settings of `textwrap` are now in the
+/// [wrap](Options::wrap) field: read `options.wrap.width` instead of
+/// `options.width`, and pass `&options.wrap` to the functions of
+/// `textwrap`. The builder methods are unchanged.
+#[derive(Clone)]
+pub struct Options<
+    'a,
+    WrapAlgo = Box<dyn WrapAlgorithm>,
+    WordSep = Box<dyn WordSeparator>,
+    WordSplit = Box<dyn WordSplitter>,
+> {
+    /// The settings used to wrap the text, see the documentation
+    /// of `textwrap` for more information.
+    pub wrap: textwrap::Options<'a, WrapAlgo, WordSep, WordSplit>,
+    /// What to do with lines wider than the width.
+    /// See the [Options::overflow] method.
+    pub overflow: Overflow,
+    /// How justified lines are filled with spaces.
+    /// See the [Options::justification] method.
+    pub justification: Justification,
+    /// How the last line of justified text is aligned.
+    /// See the [Options::last_line] method.
+    pub last_line: LastLine,
+    /// What to do with the tab characters of the text.
+    /// See the [Options::tabs] method.
+    pub tabs: Tabs,
+    /// The direction of the text.
+    /// See the [Options::direction] method.
+    pub direction: Direction,
+    /// How the width of the text is measured.
+    /// See the [Options::measure] method.
+    pub measure: &'a dyn WidthMeasure,
+    /// The pattern used to pad the lines.
+    /// See the [Options::fill] method.
+    pub fill: &'a str,
+}
+
+/// What to do with the lines that can't fit in the width.
+///
+/// This may happen when words are not allowed to be broken,
+/// when the indentation is longer than the width or when
+/// a wide character can't fit at the end of the line.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Overflow {
+    /// Leave the line as is, it will stick out without padding
+    #[default]
+    SPILL,
+    /// Cut the end of the line
+    TRUNCATE,
+    /// Cut the end of the line and replace it with `…`
+    ELLIPSIS,
+    /// Fail with an [Error::LineTooWide](crate::Error::LineTooWide)
+    ERROR,
+}
+
+/// How the spaces are distributed between the words of a justified line.
+///
+/// The spaces can rarely be split exactly evenly, so some gaps get one more
+/// space than the others. When the extra spaces always end up in the same
+/// places, they can form "rivers" of blank space in a long block of text.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Justification {
+    /// Spread the extra spaces evenly along the line
+    #[default]
+    EVEN,
+    /// Put the extra spaces after the end of sentences first,
+    /// then after other punctuation marks (`,`, `;` and `:`)
+    PUNCTUATION,
+    /// Put the extra spaces on the left of even lines
+    /// and on the right of odd lines
+    ALTERNATING,
+    /// Put the extra spaces at pseudo-random places, which are always
+    /// the same for a given seed
+    RANDOM(u64),
+}
+
+/// How the last line of justified text is aligned.
+///
+/// Stretching the last line of a paragraph often leaves huge gaps
+/// between a few words, so by default it is aligned to the start
+/// of the text (to the left, unless the text is right-to-left).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum LastLine {
+    /// Align the last line to the start of the text
+    /// (see [Alignment::START])
+    #[default]
+    START,
+    /// Align the last line to the end of the text
+    /// (see [Alignment::END])
+    END,
+    /// Align the last line to the left
+    LEFT,
+    /// Align the last line to the right
+    RIGHT,
+    /// Center the last line
+    CENTER,
+    /// Justify the last line like the others
+    JUSTIFY,
+    /// Justify the last line only if it already fills at least
+    /// this percentage of the width, otherwise align it to the start
+    FILLED(usize),
+}
+
+/// What to do with the tab characters.
+///
+/// Tabs don't have a width of their own, so they are dealt with
+/// before the text is wrapped.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Tabs {
+    /// Replace each tab with spaces up to the next tab stop,
+    /// the stops being every `n` columns from the start of each
+    /// line of the text (or of the cell, with columns)
+    EXPAND(usize),
+    /// Replace each tab with a character
+    REPLACE(char),
+    /// Fail with an [Error::TabCharacter](crate::Error::TabCharacter)
+    ERROR,
+}
+
+impl Default for Tabs {
+    /// Tab stops every 8 columns, like most terminals.
+    fn default() -> Self {
+        Tabs::EXPAND(8)
+    }
+}
+
+/// The direction in which the text is read.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub enum Direction {
+    /// Left-to-right, like in English
+    #[default]
+    LTR,
+    /// Right-to-left, like in Arabic or Hebrew: the columns are laid out
+    /// from right to left, and [Alignment::START] aligns to the right
+    RTL,
+}
+
+impl LastLine {
+    // the alignment of a last line of `line_width` in `width`
+    pub(crate) fn alignment(self, line_width: usize, width: usize) -> Alignment {
+        match self {
+            LastLine::START => Alignment::START,
+            LastLine::END => Alignment::END,
+            LastLine::LEFT => Alignment::LEFT,
+            LastLine::RIGHT => Alignment::RIGHT,
+            LastLine::CENTER => Alignment::CENTER,
+            LastLine::JUSTIFY => Alignment::JUSTIFY,
+            LastLine::FILLED(percent) => {
+                if line_width * 100 >= width * percent {
+                    Alignment::JUSTIFY
+                } else {
+                    Alignment::START
+                }
+            }
+        }
+    }
+}
+
+impl<'a> Options<'a, OptimalFit, UnicodeBreakProperties, HyphenSplitter> {
+    /// Creates new options with the specified width and
+    /// the default settings of `textwrap`.
+    ///
+    /// Lines wider than the width are left as is ([Overflow::SPILL]).
+    pub fn new(width: usize) -> Self {
+        Self::from(textwrap::Options::new(width))
+    }
+}
+
+impl<'a, WrapAlgo, WordSep, WordSplit> Options<'a, WrapAlgo, WordSep, WordSplit> {
+    /// Changes the indentation of the first line.
+    /// See [textwrap::Options::initial_indent].
+    pub fn initial_indent(mut self, indent: &'a str) -> Self {
+        self.wrap = self.wrap.initial_indent(indent);
+        return self;
+    }
+
+    /// Changes the indentation of the other lines.
+    /// See [textwrap::Options::subsequent_indent].
+    pub fn subsequent_indent(mut self, indent: &'a str) -> Self {
+        self.wrap = self.wrap.subsequent_indent(indent);
+        return self;
+    }
+
+    /// Allows or forbids breaking long words.
+    /// See [textwrap::Options::break_words].
+    pub fn break_words(mut self, setting: bool) -> Self {
+        self.wrap = self.wrap.break_words(setting);
+        return self;
+    }
+
+    /// Changes the way words are separated.
+    /// See [textwrap::Options::word_separator].
+    pub fn word_separator<NewWordSep>(
+        self,
+        word_separator: NewWordSep,
+    ) -> Options<'a, WrapAlgo, NewWordSep, WordSplit> {
+        self.map_wrap(|wrap| wrap.word_separator(word_separator))
+    }
+
+    /// Changes the wrapping algorithm.
+    /// See [textwrap::Options::wrap_algorithm].
+    ///
+    /// The words are measured with [Options::measure] for the algorithms
+    /// of `textwrap`, but not for other implementations of
+    /// [WrapAlgorithm](textwrap::wrap_algorithms::WrapAlgorithm).
+    pub fn wrap_algorithm<NewWrapAlgo>(
+        self,
+        wrap_algorithm: NewWrapAlgo,
+    ) -> Options<'a, NewWrapAlgo, WordSep, WordSplit> {
+        self.map_wrap(|wrap| wrap.wrap_algorithm(wrap_algorithm))
+    }
+
+    /// Changes the way words are split.
+    /// See [textwrap::Options::word_splitter].
+    pub fn word_splitter<NewWordSplit>(
+        self,
+        word_splitter: NewWordSplit,
+    ) -> Options<'a, WrapAlgo, WordSep, NewWordSplit> {
+        self.map_wrap(|wrap| wrap.word_splitter(word_splitter))
+    }
+
+    /// Changes what happens to lines wider than the width.
+    ///
+    /// With [Overflow::ERROR], the functions that can't return an error
+    /// (like [align()](crate::align()) or [columns()](crate::columns()))
+    /// will panic, you should use their `try_` version instead.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        return self;
+    }
+
+    /// Changes the way spaces are added to justified lines.
+    pub fn justification(mut self, justification: Justification) -> Self {
+        self.justification = justification;
+        return self;
+    }
+
+    /// Changes the alignment of the last line of justified text.
+    pub fn last_line(mut self, last_line: LastLine) -> Self {
+        self.last_line = last_line;
+        return self;
+    }
+
+    /// Changes what happens to the tab characters.
+    ///
+    /// With [Tabs::ERROR], the functions that can't return an error
+    /// will panic, you should use their `try_` version instead.
+    pub fn tabs(mut self, tabs: Tabs) -> Self {
+        self.tabs = tabs;
+        return self;
+    }
+
+    /// Changes the direction of the text.
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        return self;
+    }
+
+    /// Changes the way the width of the text is measured.
+    ///
+    /// By default, the characters are measured with [UnicodeWidth].
+    /// Use [CjkWidth](crate::CjkWidth) to treat the characters of
+    /// ambiguous width as wide characters, or your own [WidthMeasure].
+    pub fn measure(mut self, measure: &'a dyn WidthMeasure) -> Self {
+        self.measure = measure;
+        return self;
+    }
+
+    /// Changes the pattern used to pad the lines, instead of spaces.
+    ///
+    /// The pattern is repeated over the space added on each side of
+    /// a line to align it, leaving a space next to the text, so that
+    /// `.` gives leaders like `Chapter 1 ........ 12` and `=` gives
+    /// headings like `==== Title ====`. The pattern starts over at
+    /// the start of each line, so it lines up from one line to the
+    /// next. Empty lines and the spaces between justified words are
+    /// not filled. The pattern can be styled with escape sequences,
+    /// its style is closed at the end of the padding.
+    ///
+    /// Each column of a [Layout](crate::Layout) can also have its own
+    /// pattern, see [Layout::fill](crate::Layout::fill).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use textflow::{align, Options};
+    /// use textflow::Alignment::*;
+    ///
+    /// let options = Options::new(15).fill("=");
+    /// assert_eq!(align("Title", CENTER, options), "==== Title ====");
+    /// ```
+    pub fn fill(mut self, pattern: &'a str) -> Self {
+        self.fill = pattern;
+        return self;
+    }
+
+    // the settings used to align each line
+    pub(crate) fn line_settings(&self) -> LineSettings<'a> {
+        LineSettings {
+            overflow: self.overflow,
+            justification: self.justification,
+            last_line: self.last_line,
+            direction: self.direction,
+            measure: self.measure,
+            fill: self.fill,
+        }
+    }
+
+    // changes the textwrap options, keeping the other settings
+    fn map_wrap<NewWrapAlgo, NewWordSep, NewWordSplit>(
+        self,
+        f: impl FnOnce(
+            textwrap::Options<'a, WrapAlgo, WordSep, WordSplit>,
+        ) -> textwrap::Options<'a, NewWrapAlgo, NewWordSep, NewWordSplit>,
+    ) -> Options<'a, NewWrapAlgo, NewWordSep, NewWordSplit> {
+        Options {
+            wrap: f(self.wrap),
+            overflow: self.overflow,
+            justification: self.justification,
+            last_line: self.last_line,
+            tabs: self.tabs,
+            direction: self.direction,
+            measure: self.measure,
+            fill: self.fill,
+        }
+    }
+}
+
+impl<WrapAlgo, WordSep, WordSplit> fmt::Debug for Options<'_, WrapAlgo, WordSep, WordSplit>
+where
+    WrapAlgo: fmt::Debug,
+    WordSep: fmt::Debug,
+    WordSplit: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Options")
+            .field("wrap", &self.wrap)
+            .field("overflow", &self.overflow)
+            .field("justification", &self.justification)
+            .field("last_line", &self.last_line)
+            .field("tabs", &self.tabs)
+            .field("direction", &self.direction)
+            // measures don't have to implement `Debug`
+            .field("measure", &format_args!("<dyn WidthMeasure>"))
+            .field("fill", &self.fill)
+            .finish()
+    }
+}
+
+impl<'a, WrapAlgo, WordSep, WordSplit> From<textwrap::Options<'a, WrapAlgo, WordSep, WordSplit>>
+    for Options<'a, WrapAlgo, WordSep, WordSplit>
+{
+    fn from(wrap: textwrap::Options<'a, WrapAlgo, WordSep, WordSplit>) -> Self {
+        Self {
+            wrap,
+            overflow: Overflow::default(),
+            justification: Justification::default(),
+            last_line: LastLine::default(),
+            tabs: Tabs::default(),
+            direction: Direction::default(),
+            measure: &UnicodeWidth,
+            fill: " ",
+        }
+    }
+}
+
+impl<'a, WrapAlgo, WordSep, WordSplit> From<&'a textwrap::Options<'a, WrapAlgo, WordSep, WordSplit>>
+    for Options<'a, WrapAlgo, WordSep, WordSplit>
+where
+    WrapAlgo: Clone,
+    WordSep: Clone,
+    WordSplit: Clone,
+{
+    fn from(wrap: &'a textwrap::Options<'a, WrapAlgo, WordSep, WordSplit>) -> Self {
+        Self::from(textwrap::Options::from(wrap))
+    }
+}
+
+impl<'a, WrapAlgo, WordSep, WordSplit> From<&'a Options<'a, WrapAlgo, WordSep, WordSplit>>
+    for Options<'a, WrapAlgo, WordSep, WordSplit>
+where
+    WrapAlgo: Clone,
+    WordSep: Clone,
+    WordSplit: Clone,
+{
+    fn from(options: &'a Options<'a, WrapAlgo, WordSep, WordSplit>) -> Self {
+        options.clone()
+    }
+}
+
+impl<'a> From<usize> for Options<'a, OptimalFit, UnicodeBreakProperties, HyphenSplitter> {
+    fn from(width: usize) -> Self {
+        Self::new(width)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let options = Options::new(20);
+
+        assert_eq!(options.wrap.width, 20);
+        assert_eq!(options.overflow, Overflow::SPILL);
+    }
+
+    #[test]
+    fn test_builder() {
+        let options = Options::new(20)
+            .initial_indent("* ")
+            .subsequent_indent("  ")
+            .break_words(false)
+            .overflow(Overflow::TRUNCATE)
+            .justification(Justification::RANDOM(7))
+            .last_line(LastLine::CENTER)
+            .tabs(Tabs::REPLACE(' '))
+            .direction(Direction::RTL)
+            .measure(&crate::CjkWidth)
+            .fill(".")
+            .word_separator(textwrap::word_separators::AsciiSpace)
+            .word_splitter(textwrap::word_splitters::NoHyphenation)
+            .wrap_algorithm(textwrap::wrap_algorithms::FirstFit);
+
+        assert_eq!(options.wrap.width, 20);
+        assert_eq!(options.wrap.initial_indent, "* ");
+        assert_eq!(options.wrap.subsequent_indent, "  ");
+        assert!(!options.wrap.break_words);
+        // kept when the types change
+        assert_eq!(options.overflow, Overflow::TRUNCATE);
+        assert_eq!(options.justification, Justification::RANDOM(7));
+        assert_eq!(options.last_line, LastLine::CENTER);
+        assert_eq!(options.tabs, Tabs::REPLACE(' '));
+        assert_eq!(options.direction, Direction::RTL);
+        assert_eq!(options.measure.width("±"), 2);
+        assert_eq!(options.fill, ".");
+    }
+
+    #[test]
+    fn test_debug() {
+        // a measure without `Debug`
+        struct Narrow;
+        impl WidthMeasure for Narrow {
+            fn grapheme_width(&self, _: &str) -> usize {
+                1
+            }
+        }
+
+        let debug = format!("{:?}", Options::new(20).measure(&Narrow));
+        assert!(debug.starts_with("Options { wrap: Options { width: 20,"));
+        assert!(debug.contains("measure: <dyn WidthMeasure>, fill: \" \" }"));
+    }
+
+    #[test]
+    fn test_from() {
+        let from_width: Options<'_, _, _, _> = 12.into();
+        assert_eq!(from_width.wrap.width, 12);
+
+        let wrap = textwrap::Options::new(34).initial_indent("> ");
+
+        let from_ref = Options::from(&wrap);
+        assert_eq!(from_ref.wrap.width, 34);
+        assert_eq!(from_ref.wrap.initial_indent, "> ");
+
+        let from_value = Options::from(wrap);
+        assert_eq!(from_value.wrap.width, 34);
+        assert_eq!(from_value.overflow, Overflow::SPILL);
+    }
+}
@@ -0,0 +1,428 @@
+// hand-written parser for the layout patterns (see `Layout::from_pattern`)
+
+use crate::Alignment;
+use crate::Error;
+use crate::Layout;
+use crate::VerticalAlignment;
+
+// the optional parts of a column, in the order they are written
+const PARTS: [&str; 6] = [
+    "an alignment",
+    "a vertical alignment",
+    "a width",
+    "`[`",
+    "`(`",
+    "`*`",
+];
+
+// a column as written in a pattern
+#[derive(Default)]
+struct ColumnPattern {
+    alignment: Alignment,
+    vertical_alignment: VerticalAlignment,
+    width: Width,
+    min: Option<usize>,
+    max: Option<usize>,
+    fill: Option<String>,
+    repeat: bool,
+}
+
+#[derive(Default)]
+enum Width {
+    // default column size is 1fr
+    #[default]
+    DEFAULT,
+    FRACTIONAL(usize),
+    FIXED(usize),
+    PERCENT(usize),
+    AUTO,
+}
+
+// parses a whole pattern
+pub fn parse(pattern: &str) -> Result<Layout, Error> {
+    let mut parsed = Layout::new();
+    let mut already_repeated = false;
+
+    for (position, token) in split_columns(pattern) {
+        if token.is_empty() {
+            // skip multiple spaces
+            continue;
+        }
+
+        let mut parser = ColumnParser::new(token);
+        let column = parser.column().map_err(|expected| Error::InvalidColumn {
+            token: String::from(token),
+            position,
+            offset: position + parser.offset,
+            expected,
+        })?;
+
+        parsed = column.add_to(parsed);
+
+        if column.repeat {
+            if already_repeated {
+                return Err(Error::DuplicateRepeat {
+                    token: String::from(token),
+                    position,
+                });
+            }
+            parsed = parsed.repeat();
+            already_repeated = true;
+        }
+    }
+
+    return Ok(parsed);
+}
+
+// parses an alignment on its own
+pub fn parse_alignment(token: &str) -> Option<Alignment> {
+    let mut parser = ColumnParser::new(token);
+    match parser.alignment() {
+        Ok(Some(alignment)) if parser.at_end() => Some(alignment),
+        _ => None,
+    }
+}
+
+impl ColumnPattern {
+    // adds the column to a layout (without the repeating flag)
+    fn add_to(&self, layout: Layout) -> Layout {
+        let mut layout = match self.width {
+            Width::DEFAULT => layout.fractional(1, self.alignment),
+            Width::FRACTIONAL(size) => layout.fractional(size, self.alignment),
+            Width::FIXED(size) => layout.fixed(size, self.alignment),
+            Width::PERCENT(size) => layout.percent(size, self.alignment),
+            Width::AUTO => layout.auto(self.alignment),
+        };
+        layout = layout.vertical_align(self.vertical_alignment);
+        if let Some(min) = self.min {
+            layout = layout.min_width(min);
+        }
+        if let Some(max) = self.max {
+            layout = layout.max_width(max);
+        }
+        if let Some(fill) = &self.fill {
+            layout = layout.fill(fill);
+        }
+        return layout;
+    }
+}
+
+// reads a column one character at a time, the errors
+// being what was expected at the current offset
+struct ColumnParser<'p> {
+    token: &'p str,
+    offset: usize,
+}
+
+impl<'p> ColumnParser<'p> {
+    fn new(token: &'p str) -> Self {
+        Self { token, offset: 0 }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.token[self.offset..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let ch = self.peek()?;
+        self.offset += ch.len_utf8();
+        return Some(ch);
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        if self.peek() == Some(expected) {
+            self.bump();
+            return true;
+        }
+        return false;
+    }
+
+    fn at_end(&self) -> bool {
+        self.offset == self.token.len()
+    }
+
+    fn column(&mut self) -> Result<ColumnPattern, String> {
+        let mut column = ColumnPattern::default();
+        // the first part that can still come
+        let mut next = 0;
+
+        if let Some(alignment) = self.alignment()? {
+            column.alignment = alignment;
+            next = 1;
+        }
+        if let Some(vertical_alignment) = self.vertical_alignment() {
+            column.vertical_alignment = vertical_alignment;
+            next = 2;
+        }
+        if let Some(width) = self.width()? {
+            column.width = width;
+            next = 3;
+        }
+        if self.eat('[') {
+            (column.min, column.max) = self.constraints()?;
+            next = 4;
+        }
+        if self.eat('(') {
+            column.fill = Some(self.fill()?);
+            next = 5;
+        }
+        if self.eat('*') {
+            column.repeat = true;
+            next = 6;
+        }
+
+        if !self.at_end() {
+            let mut parts = PARTS[next..].to_vec();
+            parts.push("the end of the column");
+            return Err(one_of(&parts));
+        }
+        return Ok(column);
+    }
+
+    fn alignment(&mut self) -> Result<Option<Alignment>, String> {
+        let alignment = match self.peek() {
+            Some('<') => Alignment::LEFT,
+            Some('^') => Alignment::CENTER,
+            Some('>') => Alignment::RIGHT,
+            Some('{') => Alignment::START,
+            Some('}') => Alignment::END,
+            Some('=') => Alignment::JUSTIFY,
+            Some(anchor @ ('.' | ':' | ',')) => Alignment::ANCHOR(anchor),
+            Some('@') => {
+                self.bump();
+                let anchor = self.character("an anchor character")?;
+                return Ok(Some(Alignment::ANCHOR(anchor)));
+            }
+            _ => return Ok(None),
+        };
+        self.bump();
+        return Ok(Some(alignment));
+    }
+
+    fn vertical_alignment(&mut self) -> Option<VerticalAlignment> {
+        let vertical_alignment = match self.peek()? {
+            '\'' => VerticalAlignment::TOP,
+            '~' => VerticalAlignment::MIDDLE,
+            '_' => VerticalAlignment::BOTTOM,
+            _ => return None,
+        };
+        self.bump();
+        return Some(vertical_alignment);
+    }
+
+    fn width(&mut self) -> Result<Option<Width>, String> {
+        if self.peek() == Some('-') {
+            let mut hyphens = 0;
+            while self.eat('-') {
+                hyphens += 1;
+            }
+            return Ok(Some(Width::FRACTIONAL(hyphens)));
+        }
+        if self.eat('a') {
+            return Ok(Some(Width::AUTO));
+        }
+
+        let Some(size) = self.number()? else {
+            return Ok(None);
+        };
+        if self.eat('%') {
+            return Ok(Some(Width::PERCENT(size)));
+        }
+        if self.eat('-') {
+            return Ok(Some(Width::FRACTIONAL(size)));
+        }
+        return Ok(Some(Width::FIXED(size)));
+    }
+
+    // `min..max]`, after the `[`
+    fn constraints(&mut self) -> Result<(Option<usize>, Option<usize>), String> {
+        let min = self.number()?;
+        if self.token[self.offset..].starts_with("..") {
+            self.offset += 2;
+        } else if min.is_none() {
+            return Err(one_of(&["a minimum width", "`..`"]));
+        } else {
+            return Err(String::from("`..`"));
+        }
+
+        let max = self.number()?;
+        if !self.eat(']') {
+            if max.is_none() {
+                return Err(one_of(&["a maximum width", "`]`"]));
+            } else {
+                return Err(String::from("`]`"));
+            }
+        }
+
+        return Ok((min, max));
+    }
+
+    // the fill pattern until `)`, after the `(`
+    fn fill(&mut self) -> Result<String, String> {
+        let mut fill = String::new();
+        loop {
+            match self.peek() {
+                Some(')') => {
+                    self.bump();
+                    return Ok(fill);
+                }
+                // the parentheses inside must be escaped
+                Some('(') | None => return Err(String::from("`)`")),
+                Some(_) => fill.push(self.character("`)`")?),
+            }
+        }
+    }
+
+    // a character, which may be escaped with a backslash
+    fn character(&mut self, expected: &str) -> Result<char, String> {
+        match self.bump() {
+            Some('\\') => self
+                .bump()
+                .ok_or_else(|| String::from("an escaped character")),
+            Some(ch) => Ok(ch),
+            None => Err(String::from(expected)),
+        }
+    }
+
+    fn number(&mut self) -> Result<Option<usize>, String> {
+        let start = self.offset;
+        while self.peek().is_some_and(|ch| ch.is_ascii_digit()) {
+            self.bump();
+        }
+
+        let digits = &self.token[start..self.offset];
+        if digits.is_empty() {
+            return Ok(None);
+        }
+        return match digits.parse() {
+            Ok(number) => Ok(Some(number)),
+            Err(_) => {
+                self.offset = start;
+                Err(String::from("a smaller number"))
+            }
+        };
+    }
+}
+
+// lists the things that could have come instead
+fn one_of(parts: &[&str]) -> String {
+    match parts.split_last() {
+        Some((last, [])) => String::from(*last),
+        Some((last, others)) => others.join(", ") + " or " + last,
+        None => String::new(),
+    }
+}
+
+// splits a pattern at the spaces that are not escaped,
+// with the position of each column in the pattern
+fn split_columns(pattern: &str) -> Vec<(usize, &str)> {
+    let mut columns = Vec::new();
+    let mut start = 0;
+    let mut escaped = false;
+
+    for (i, ch) in pattern.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if ch == '\\' {
+            escaped = true;
+        } else if ch == ' ' {
+            columns.push((start, &pattern[start..i]));
+            start = i + 1;
+        }
+    }
+    columns.push((start, &pattern[start..]));
+
+    return columns;
+}
+
+// puts a backslash before the backslashes and the `special` characters
+pub fn escape(text: &str, special: &[char]) -> String {
+    let mut escaped = String::new();
+    for ch in text.chars() {
+        if ch == '\\' || special.contains(&ch) {
+            escaped.push('\\');
+        }
+        escaped.push(ch);
+    }
+    return escaped;
+}
+
+#[test]
+fn test_parse() {
+    assert_eq!(
+        parse(">'3-[2..](\\ -\\)) @\\ _10%[..8]* a(\\\\)"),
+        Ok(Layout::new()
+            .fractional(3, Alignment::RIGHT)
+            .min_width(2)
+            .fill(" -)")
+            .percent(10, Alignment::ANCHOR(' '))
+            .vertical_align(VerticalAlignment::BOTTOM)
+            .max_width(8)
+            .repeat()
+            .auto(Alignment::LEFT)
+            .fill("\\"))
+    );
+    assert_eq!(parse("  "), Ok(Layout::new()));
+}
+
+#[test]
+fn test_parse_errors() {
+    // the offset and what was expected there
+    let error = |pattern| match parse(pattern) {
+        Err(Error::InvalidColumn {
+            offset, expected, ..
+        }) => Some((offset, expected)),
+        _ => None,
+    };
+    let expected = |offset, expected: &str| Some((offset, String::from(expected)));
+
+    assert_eq!(
+        error("^7 +8 9"),
+        expected(
+            3,
+            "an alignment, a vertical alignment, a width, `[`, `(`, `*` or the end of the column"
+        )
+    );
+    assert_eq!(
+        error("<~5x"),
+        expected(3, "`[`, `(`, `*` or the end of the column")
+    );
+    assert_eq!(
+        error("5* 3-%"),
+        expected(5, "`[`, `(`, `*` or the end of the column")
+    );
+    assert_eq!(error("(.)*+"), expected(4, "the end of the column"));
+    assert_eq!(error("5[2]"), expected(3, "`..`"));
+    assert_eq!(error("5[.2]"), expected(2, "a minimum width or `..`"));
+    assert_eq!(error("5[2..8"), expected(6, "`]`"));
+    assert_eq!(error("5[..)"), expected(4, "a maximum width or `]`"));
+    assert_eq!(error("<(.(.)"), expected(3, "`)`"));
+    assert_eq!(error("<(.\\"), expected(4, "an escaped character"));
+    assert_eq!(error("< @"), expected(3, "an anchor character"));
+    assert_eq!(
+        error("99999999999999999999999"),
+        expected(0, "a smaller number")
+    );
+    // the duplicate repeat is not a syntax error
+    assert_eq!(error("5* 3*"), None);
+}
+
+#[test]
+fn test_parse_diagnostic() {
+    let pattern = "^7 +8 9";
+    assert_eq!(
+        parse(pattern).unwrap_err().diagnostic(pattern).unwrap(),
+        "^7 +8 9\n   ^ expected an alignment, a vertical alignment, a width, `[`, `(`, `*` or the end of the column"
+    );
+}
+
+#[test]
+fn test_parse_alignment() {
+    assert_eq!(parse_alignment("^"), Some(Alignment::CENTER));
+    assert_eq!(parse_alignment(","), Some(Alignment::ANCHOR(',')));
+    assert_eq!(parse_alignment("@;"), Some(Alignment::ANCHOR(';')));
+    assert_eq!(parse_alignment("@\\ "), Some(Alignment::ANCHOR(' ')));
+    assert_eq!(parse_alignment("<<"), None);
+    assert_eq!(parse_alignment("@"), None);
+    assert_eq!(parse_alignment(""), None);
+}
@@ -0,0 +1,446 @@
+use crate::columns::{align_grid, preferred_extent, preferred_width, wrap_columns};
+use crate::lines::anchor_extents;
+
+use crate::Direction;
+use crate::Error;
+use crate::Layout;
+
+// box-drawing characters
+const VERTICAL: char = '│';
+const HORIZONTAL: char = '─';
+const DOUBLE_HORIZONTAL: char = '═';
+
+// the characters used to draw a horizontal rule
+struct Rule {
+    left: char,
+    line: char,
+    junction: char,
+    right: char,
+}
+
+const TOP: Rule = Rule {
+    left: '┌',
+    line: HORIZONTAL,
+    junction: '┬',
+    right: '┐',
+};
+const HEADER: Rule = Rule {
+    left: '╞',
+    line: DOUBLE_HORIZONTAL,
+    junction: '╪',
+    right: '╡',
+};
+const SEPARATOR: Rule = Rule {
+    left: '├',
+    line: HORIZONTAL,
+    junction: '┼',
+    right: '┤',
+};
+const BOTTOM: Rule = Rule {
+    left: '└',
+    line: HORIZONTAL,
+    junction: '┴',
+    right: '┘',
+};
+
+/// Wraps and aligns text in a table with borders.
+///
+/// `rows` may be an array or a vector of rows, each row
+/// being an array or a vector of strings. The number of columns
+/// is the number of cells in the longest row, and shorter rows are
+/// completed with empty cells.
+///
+/// If `header` is `true`, the first row is separated from the
+/// others by a double line.
+///
+/// `width_or_options` can either be an integer, [textwrap::Options]
+/// or [Options](crate::Options), see the documentation of `textwrap`
+/// for more information.
+/// The width is the total width of the table, borders included:
+/// each column takes three more characters for the separator and
+/// the spaces around the text, plus one for the right border.
+///
+/// The cells are wrapped and aligned the same way as with [columns()](crate::columns()).
+///
+/// # Examples
+///
+/// ```
+/// use textflow::table;
+/// use textflow::Layout;
+///
+/// fn main() {
+///     let rows = [
+///         vec!["Name", "Description"],
+///         vec!["align", "Aligns text to the left, to the right, at the center or justifies it."],
+///         vec!["columns", "Prints text side by side."],
+///     ];
+///     let layout = Layout::from_pattern("<9 <-").unwrap();
+///     println!("{}", table(rows, true, &layout, 40));
+/// }
+/// ```
+/// should display
+/// ```text
+/// ┌───────────┬──────────────────────────┐
+/// │ Name      │ Description              │
+/// ╞═══════════╪══════════════════════════╡
+/// │ align     │ Aligns text to the left, │
+/// │           │ to the right, at the     │
+/// │           │ center or justifies it.  │
+/// ├───────────┼──────────────────────────┤
+/// │ columns   │ Prints text side by      │
+/// │           │ side.                    │
+/// └───────────┴──────────────────────────┘
+/// ```
+///
+/// # Panics
+///
+/// This function panics if the number of columns doesn't match the layout,
+/// if the width is too small to fit the borders, if a line is too wide
+/// and the overflow policy is [Overflow::ERROR](crate::Overflow::ERROR)
+/// or if a cell contains a tab and the tab policy is
+/// [Tabs::ERROR](crate::Tabs::ERROR). Use [try_table()]
+/// to handle these errors instead.
+pub fn table<
+    'a,
+    Rows,
+    StringsCollection,
+    TextwrapAlgo,
+    TextwrapWordSep,
+    TextwrapWordSplit,
+    TextwrapOptions,
+>(
+    rows: Rows,
+    header: bool,
+    layout: &Layout,
+    width_or_options: TextwrapOptions,
+) -> String
+where
+    Rows: IntoIterator<Item = StringsCollection>,
+    StringsCollection: IntoIterator<Item = &'a str>,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+{
+    try_table(rows, header, layout, width_or_options).unwrap()
+}
+
+/// Wraps and aligns text in a table with borders, without panicking.
+///
+/// This is the same as [table()], but returns an [Error] when the table
+/// can't be drawn, like [try_columns()](crate::try_columns()) does.
+pub fn try_table<
+    'a,
+    Rows,
+    StringsCollection,
+    TextwrapAlgo,
+    TextwrapWordSep,
+    TextwrapWordSplit,
+    TextwrapOptions,
+>(
+    rows: Rows,
+    header: bool,
+    layout: &Layout,
+    width_or_options: TextwrapOptions,
+) -> Result<String, Error>
+where
+    Rows: IntoIterator<Item = StringsCollection>,
+    StringsCollection: IntoIterator<Item = &'a str>,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+    TextwrapOptions: Into<crate::Options<'a, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>>,
+{
+    let options = width_or_options.into();
+
+    let mut vectorised: Vec<Vec<&str>> = rows
+        .into_iter()
+        .map(|row| row.into_iter().collect())
+        .collect();
+
+    let count = vectorised.iter().map(|row| row.len()).max().unwrap_or(0);
+    if count == 0 {
+        // nothing to draw
+        return Ok(String::new());
+    }
+    for row in vectorised.iter_mut() {
+        row.resize(count, "");
+    }
+
+    let needed = borders_needed(count);
+    let available = options
+        .wrap
+        .width
+        .checked_sub(needed)
+        .ok_or(Error::NotEnoughWidth {
+            available: options.wrap.width,
+            needed,
+        })?;
+
+    // the anchors of the body are lined up across the rows,
+    // so their widest parts add up
+    let alignments = layout.alignments(count);
+    let mut extents = vec![(0, 0); count];
+    let mut header_widths = vec![0; count];
+    for (r, row) in vectorised.iter().enumerate() {
+        for (i, cell) in row.iter().enumerate() {
+            if header && r == 0 {
+                header_widths[i] = preferred_width(cell, alignments[i], &options);
+            } else {
+                let (before, after) = preferred_extent(cell, alignments[i], &options);
+                extents[i] = (extents[i].0.max(before), extents[i].1.max(after));
+            }
+        }
+    }
+    let content_widths: Vec<usize> = extents
+        .iter()
+        .zip(header_widths)
+        .map(|((before, after), header)| std::cmp::max(before + after, header))
+        .collect();
+    let mut columns = layout.resolve_with(available, &content_widths)?;
+
+    if let Some(index) = columns.iter().position(|column| column.width == 0) {
+        return Err(Error::ZeroWidthColumn { index });
+    }
+
+    let mut wrapped: Vec<Vec<_>> = vectorised
+        .iter()
+        .map(|row| wrap_columns(row, &columns, &options))
+        .collect::<Result<_, _>>()?;
+    if options.direction == Direction::RTL {
+        // the first column is on the right
+        columns.reverse();
+        for row in wrapped.iter_mut() {
+            row.reverse();
+        }
+    }
+
+    // the anchors are lined up across all the rows, except the header
+    let body = if header { &wrapped[1..] } else { &wrapped[..] };
+    let anchors: Vec<(usize, usize)> = columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            let lines = body.iter().flat_map(|row| row[i].iter());
+            anchor_extents(
+                lines.map(|(line, _)| line.as_ref()),
+                column,
+                options.measure,
+            )
+        })
+        .collect();
+
+    let mut formatted = String::new();
+
+    push_rule(&mut formatted, &TOP, &columns);
+
+    for (i, row) in wrapped.into_iter().enumerate() {
+        if i != 0 {
+            if header && i == 1 {
+                push_rule(&mut formatted, &HEADER, &columns);
+            } else {
+                push_rule(&mut formatted, &SEPARATOR, &columns);
+            }
+        }
+
+        // the header is lined up on its own
+        let shared = if header && i == 0 {
+            None
+        } else {
+            Some(&anchors[..])
+        };
+        for line in align_grid(row, &columns, shared, &options)? {
+            formatted.push('\n');
+            for cell in line {
+                formatted.push(VERTICAL);
+                formatted.push(' ');
+                formatted.push_str(&cell);
+                formatted.push(' ');
+            }
+            formatted.push(VERTICAL);
+        }
+
+        formatted.push('\n');
+    }
+
+    push_rule(&mut formatted, &BOTTOM, &columns);
+
+    return Ok(formatted);
+}
+
+// space taken by the borders and the padding inside the cells
+fn borders_needed(columns: usize) -> usize {
+    3 * columns + 1
+}
+
+// draws an horizontal rule (without the line feed)
+fn push_rule(formatted: &mut String, rule: &Rule, columns: &[crate::layout::Column]) {
+    formatted.push(rule.left);
+    for (i, column) in columns.iter().enumerate() {
+        if i != 0 {
+            formatted.push(rule.junction);
+        }
+        for _ in 0..(column.width + 2) {
+            formatted.push(rule.line);
+        }
+    }
+    formatted.push(rule.right);
+}
+
+#[test]
+fn test_table() {
+    // the doctest
+    let rows = [
+        vec!["Name", "Description"],
+        vec![
+            "align",
+            "Aligns text to the left, to the right, at the center or justifies it.",
+        ],
+        vec!["columns", "Prints text side by side."],
+    ];
+
+    let layout = Layout::from_pattern("<9 <-").unwrap();
+
+    let expected = String::from(
+        "┌───────────┬──────────────────────────┐
+│ Name      │ Description              │
+╞═══════════╪══════════════════════════╡
+│ align     │ Aligns text to the left, │
+│           │ to the right, at the     │
+│           │ center or justifies it.  │
+├───────────┼──────────────────────────┤
+│ columns   │ Prints text side by      │
+│           │ side.                    │
+└───────────┴──────────────────────────┘",
+    );
+
+    assert_eq!(table(rows, true, &layout, 40), expected);
+}
+
+#[test]
+fn test_table_without_header() {
+    let rows = [vec!["a", "b"], vec!["c"]];
+
+    let layout = Layout::from_pattern("^ >").unwrap();
+
+    let expected = String::from(
+        "┌────┬────┐
+│ a  │  b │
+├────┼────┤
+│ c  │    │
+└────┴────┘",
+    );
+
+    assert_eq!(table(rows, false, &layout, 11), expected);
+}
+
+#[test]
+fn test_table_auto() {
+    let rows = [
+        vec!["#", "Item"],
+        vec!["1", "Apples"],
+        vec!["10", "Oranges"],
+    ];
+
+    let layout = Layout::from_pattern(">a -").unwrap();
+
+    let expected = String::from(
+        "┌────┬────────────┐
+│  # │ Item       │
+╞════╪════════════╡
+│  1 │ Apples     │
+├────┼────────────┤
+│ 10 │ Oranges    │
+└────┴────────────┘",
+    );
+
+    assert_eq!(table(rows, true, &layout, 19), expected);
+
+    // the short column keeps its width when the other one shrinks
+    let rows = [
+        vec!["id", "Description"],
+        vec!["1", "A description much too long to fit on a single line"],
+    ];
+    let layout = Layout::from_pattern("a a").unwrap();
+    let shrunk = try_table(rows, true, &layout, 30).unwrap();
+    assert!(shrunk.starts_with("┌────┬"));
+    assert!(shrunk
+        .lines()
+        .nth(1)
+        .unwrap()
+        .starts_with("│ id │ Description "));
+}
+
+#[test]
+fn test_table_anchor() {
+    let rows = [
+        vec!["Item", "Price"],
+        vec!["Tea", "3.5"],
+        vec!["Cake", "12.25"],
+        vec!["Jam", "4"],
+    ];
+
+    let layout = Layout::from_pattern("<- .8").unwrap();
+
+    let expected = String::from(
+        "┌────────┬──────────┐
+│ Item   │    Price │
+╞════════╪══════════╡
+│ Tea    │     3.5  │
+├────────┼──────────┤
+│ Cake   │    12.25 │
+├────────┼──────────┤
+│ Jam    │     4    │
+└────────┴──────────┘",
+    );
+
+    assert_eq!(table(rows, true, &layout, 21), expected);
+
+    // the automatic width fits the lined up values
+    let layout = Layout::from_pattern("<a .a").unwrap();
+    assert_eq!(
+        table([["a", "1.5"], ["b", "12345"]], false, &layout, 30),
+        "┌───┬─────────┐
+│ a │     1.5 │
+├───┼─────────┤
+│ b │ 12345   │
+└───┴─────────┘"
+    );
+}
+
+#[test]
+fn test_table_empty() {
+    let rows: [Vec<&str>; 0] = [];
+
+    assert_eq!(table(rows, true, &Layout::default(), 20), String::new());
+}
+
+#[test]
+fn test_try_table() {
+    let layout = Layout::from_pattern("- -").unwrap();
+
+    assert_eq!(
+        try_table([vec!["a", "b", "c"]], false, &layout, 20),
+        Err(Error::TooManyColumns {
+            expected: 2,
+            actual: 3
+        })
+    );
+    assert_eq!(
+        try_table([vec!["a", "b"]], false, &layout, 6),
+        Err(Error::NotEnoughWidth {
+            available: 6,
+            needed: 7
+        })
+    );
+    assert_eq!(
+        try_table([vec!["a", "b"]], false, &layout, 8),
+        Ok(String::from("┌───┬───┐\n│ a │ b │\n└───┴───┘"))
+    );
+}
+
+#[test]
+fn test_borders_needed() {
+    assert_eq!(borders_needed(1), 4);
+    assert_eq!(borders_needed(3), 10);
+}
@@ -1,5 +1,7 @@
 // behind the scene
 
+use std::borrow::Cow;
+
 // divides `number` into `into` integer parts the most evenly possible
 pub fn split_evenly(number: usize, into: usize) -> Vec<usize> {
     // steps from 0 to `number`, separated by `number`/`into`
@@ -26,7 +28,91 @@ fn test_split_evenly() {
     // edge cases
     assert_eq!(split_evenly(3, 7), vec![0, 1, 0, 1, 0, 1, 0]);
 
-    assert_eq!(split_evenly(10, 0), vec![]);
+    assert_eq!(split_evenly(10, 0), Vec::<usize>::new());
+}
+
+// divides `number` into parts proportional to `weights`, the most evenly possible
+pub fn split_weighted(number: usize, weights: &[usize]) -> Vec<usize> {
+    let total: usize = weights.iter().sum();
+    if total == 0 {
+        return vec![0; weights.len()];
+    }
+
+    // same as `split_evenly`, but the steps are placed
+    // according to the cumulated weights
+    let mut steps: Vec<usize> = vec![0];
+    let mut cumulated = 0;
+    for weight in weights {
+        cumulated += weight;
+        steps.push((cumulated as f32 * (number as f32 / total as f32)).round() as usize);
+    }
+
+    let mut deltas = Vec::new();
+    for i in 0..weights.len() {
+        deltas.push(steps[i + 1] - steps[i]);
+    }
+
+    return deltas;
+}
+
+#[test]
+fn test_split_weighted() {
+    assert_eq!(split_weighted(10, &[1, 4]), vec![2, 8]);
+    assert_eq!(split_weighted(7, &[3, 3, 1]), vec![3, 3, 1]);
+
+    // same as `split_evenly` with equal weights
+    assert_eq!(split_weighted(23, &[1, 1, 1, 1, 1]), split_evenly(23, 5));
+
+    // edge cases
+    assert_eq!(split_weighted(5, &[0, 0]), vec![0, 0]);
+    assert_eq!(split_weighted(5, &[]), Vec::<usize>::new());
+}
+
+// reduces the largest `widths` first until they add up to `available`,
+// keeping at least 1 for the widths that aren't 0
+pub fn shrink_widest(widths: &[usize], available: usize) -> Vec<usize> {
+    if widths.iter().sum::<usize>() <= available {
+        return widths.to_vec();
+    }
+
+    // the highest cap for which the widths still fit
+    let capped = |cap: usize| -> usize { widths.iter().map(|width| (*width).min(cap)).sum() };
+    let mut cap = 0;
+    while capped(cap + 1) <= available {
+        cap += 1;
+    }
+
+    // what's left goes to the first widths above the cap
+    let mut left = available - capped(cap);
+    let mut shrunk = Vec::new();
+    for width in widths {
+        let mut share = (*width).min(cap);
+        if *width > cap && left > 0 {
+            share += 1;
+            left -= 1;
+        }
+        if *width > 0 {
+            share = share.max(1);
+        }
+        shrunk.push(share);
+    }
+
+    return shrunk;
+}
+
+#[test]
+fn test_shrink_widest() {
+    assert_eq!(shrink_widest(&[5, 90], 39), vec![5, 34]);
+    assert_eq!(shrink_widest(&[1, 41], 9), vec![1, 8]);
+    assert_eq!(shrink_widest(&[10, 12, 4], 20), vec![8, 8, 4]);
+    assert_eq!(shrink_widest(&[10, 12, 4], 21), vec![9, 8, 4]);
+
+    // fits already
+    assert_eq!(shrink_widest(&[3, 4], 10), vec![3, 4]);
+
+    // at least one character of content
+    assert_eq!(shrink_widest(&[3, 0, 4], 1), vec![1, 0, 1]);
+    assert_eq!(shrink_widest(&[], 0), Vec::<usize>::new());
 }
 
 // creates new options for textwrap
@@ -75,110 +161,331 @@ fn test_copy_textwrap_options() {
     assert_eq!(output.break_words, expected_output.break_words);
 }
 
-// invert a 2-dimensional vector
-pub fn invert_2d_vec<T>(v: &mut Vec<Vec<T>>)
+// the lines of a wrapped text, and whether they end a paragraph
+pub type WrappedLines<'t> = Vec<(Cow<'t, str>, bool)>;
+
+// deals with the tabs of a text according to the tab policy
+pub fn replace_tabs<'t>(
+    text: &'t str,
+    tabs: crate::Tabs,
+    measure: &dyn crate::WidthMeasure,
+) -> Result<Cow<'t, str>, crate::Error> {
+    let Some(position) = text.find('\t') else {
+        // nothing to do
+        return Ok(Cow::Borrowed(text));
+    };
+    match tabs {
+        crate::Tabs::EXPAND(stop) => Ok(Cow::Owned(crate::ansi::expand_tabs(text, stop, measure))),
+        crate::Tabs::REPLACE(ch) => Ok(Cow::Owned(text.replace('\t', &ch.to_string()))),
+        crate::Tabs::ERROR => Err(crate::Error::TabCharacter { position }),
+    }
+}
+
+#[test]
+fn test_replace_tabs() {
+    assert_eq!(
+        replace_tabs("a\tb", crate::Tabs::EXPAND(4), &crate::UnicodeWidth),
+        Ok(Cow::from("a   b"))
+    );
+    assert_eq!(
+        replace_tabs("a\tb", crate::Tabs::REPLACE('→'), &crate::UnicodeWidth),
+        Ok(Cow::from("a→b"))
+    );
+    assert_eq!(
+        replace_tabs("ab\t", crate::Tabs::ERROR, &crate::UnicodeWidth),
+        Err(crate::Error::TabCharacter { position: 2 })
+    );
+    assert!(matches!(
+        replace_tabs("no tabs", crate::Tabs::ERROR, &crate::UnicodeWidth),
+        Ok(Cow::Borrowed("no tabs"))
+    ));
+}
+
+// wraps a text after dealing with its tabs
+pub fn wrap_text<'t, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    text: &'t str,
+    options: &textwrap::Options<'_, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+    tabs: crate::Tabs,
+    measure: &dyn crate::WidthMeasure,
+) -> Result<WrappedLines<'t>, crate::Error>
 where
-    Vec<T>: Clone,
-    T: Default + Clone,
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
 {
-    let height = v.len();
-    let width = v.iter().map(|v| v.len()).max().unwrap();
+    return match replace_tabs(text, tabs, measure)? {
+        Cow::Borrowed(text) => Ok(wrap_paragraphs(text, options, measure)),
+        // the lines can't borrow from the new text
+        Cow::Owned(text) => Ok(wrap_paragraphs(&text, options, measure)
+            .into_iter()
+            .map(|(line, end)| (Cow::Owned(line.into_owned()), end))
+            .collect()),
+    };
+}
 
-    // expand the vector into a square
-    let size = if height > width {
-        for u in v.iter_mut() {
-            u.resize_with(height, Default::default);
-        }
-        height
-    } else {
-        for u in v.iter_mut() {
-            u.resize_with(width, Default::default);
-        }
-        v.resize(
-            width,
-            std::iter::repeat_with(Default::default)
-                .take(width)
-                .collect(),
+// wraps each paragraph of the text (separated by line feeds) on its own,
+// and tells which lines end a paragraph
+pub fn wrap_paragraphs<'t, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    text: &'t str,
+    options: &textwrap::Options<'_, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+    measure: &dyn crate::WidthMeasure,
+) -> WrappedLines<'t>
+where
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+{
+    let mut wrapped = Vec::new();
+
+    for paragraph in text.split('\n') {
+        // only the first line of the whole text has the initial indent
+        let lines = wrap_paragraph(paragraph, options, measure, wrapped.is_empty());
+
+        let count = lines.len();
+        wrapped.extend(
+            lines
+                .into_iter()
+                .enumerate()
+                .map(|(i, line)| (line, i == count - 1)),
         );
-        width
-    };
+    }
+
+    return wrapped;
+}
+
+// a word of a paragraph, measured with the width measure
+#[derive(Debug)]
+struct Piece<'t> {
+    word: textwrap::core::Word<'t>,
+    width: usize,
+    whitespace_width: usize,
+    penalty_width: usize,
+}
+
+impl<'t> Piece<'t> {
+    fn new(word: textwrap::core::Word<'t>, measure: &dyn crate::WidthMeasure) -> Self {
+        return Self {
+            word,
+            width: crate::ansi::display_width(word.word, measure),
+            whitespace_width: measure.width(word.whitespace),
+            penalty_width: measure.width(word.penalty),
+        };
+    }
+}
+
+impl textwrap::core::Fragment for Piece<'_> {
+    fn width(&self) -> usize {
+        self.width
+    }
+
+    fn whitespace_width(&self) -> usize {
+        self.whitespace_width
+    }
 
-    // swap the values
-    for i in 0..(size - 1) {
-        for j in (1 + i)..(size) {
-            swap_2d_vec(v, (i, j), (j, i));
+    fn penalty_width(&self) -> usize {
+        self.penalty_width
+    }
+}
+
+// wraps a paragraph like `textwrap::wrap`, except that the words are
+// measured with `measure` and those too wide for a line are broken
+// between grapheme clusters
+fn wrap_paragraph<'t, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>(
+    paragraph: &'t str,
+    options: &textwrap::Options<'_, TextwrapAlgo, TextwrapWordSep, TextwrapWordSplit>,
+    measure: &dyn crate::WidthMeasure,
+    first: bool,
+) -> Vec<Cow<'t, str>>
+where
+    TextwrapAlgo: textwrap::wrap_algorithms::WrapAlgorithm + Clone,
+    TextwrapWordSep: textwrap::word_separators::WordSeparator + Clone,
+    TextwrapWordSplit: textwrap::word_splitters::WordSplitter + Clone,
+{
+    use textwrap::core::Word;
+    use textwrap::wrap_algorithms::{wrap_first_fit, wrap_optimal_fit};
+
+    let initial_width = options
+        .width
+        .saturating_sub(crate::ansi::display_width(options.initial_indent, measure));
+    let subsequent_width = options.width.saturating_sub(crate::ansi::display_width(
+        options.subsequent_indent,
+        measure,
+    ));
+
+    let words = options.word_separator.find_words(paragraph);
+    let words = textwrap::word_splitters::split_words(words, &options.word_splitter);
+    let mut pieces = Vec::new();
+    if options.break_words && !options.initial_indent.is_empty() {
+        // lets the first word go on the second line
+        // if it doesn't fit on the first one
+        pieces.push(Piece::new(Word::from(""), measure));
+    }
+    for word in words {
+        let piece = Piece::new(word, measure);
+        if !options.break_words || piece.width <= subsequent_width {
+            pieces.push(piece);
+            continue;
         }
+        let parts = crate::ansi::break_apart(word.word, subsequent_width, measure);
+        let last = parts.len() - 1;
+        pieces.extend(parts.into_iter().enumerate().map(|(i, part)| {
+            let mut broken = Word::from(part);
+            // the whitespace and the penalty go after the last part
+            if i == last {
+                broken.whitespace = word.whitespace;
+                broken.penalty = word.penalty;
+            }
+            Piece::new(broken, measure)
+        }));
     }
 
-    // truncate the vector
-    if height > width {
-        v.truncate(width)
-    } else {
-        for u in v.iter_mut() {
-            u.truncate(height);
+    let line_widths = [initial_width, subsequent_width];
+    let wrapped = match format!("{:?}", options.wrap_algorithm).as_str() {
+        "FirstFit" => wrap_first_fit(&pieces, &line_widths),
+        "OptimalFit" => wrap_optimal_fit(&pieces, &line_widths),
+        // other algorithms only work with the words of `textwrap`,
+        // measured with its own rules
+        _ => {
+            let words: Vec<Word> = pieces.iter().map(|piece| piece.word).collect();
+            let mut start = 0;
+            let mut wrapped = Vec::new();
+            for line in options.wrap_algorithm.wrap(&words, &line_widths) {
+                wrapped.push(&pieces[start..start + line.len()]);
+                start += line.len();
+            }
+            wrapped
+        }
+    };
+
+    let mut lines: Vec<Cow<'t, str>> = Vec::new();
+    let mut position = 0;
+    for line in wrapped {
+        let last = match line.last() {
+            Some(last) => last.word,
+            None => {
+                lines.push(Cow::Borrowed(""));
+                continue;
+            }
+        };
+
+        // the words are contiguous in the paragraph
+        let len = line
+            .iter()
+            .map(|piece| piece.word.word.len() + piece.word.whitespace.len())
+            .sum::<usize>()
+            - last.whitespace.len();
+
+        let indent = if first && lines.is_empty() {
+            options.initial_indent
+        } else {
+            options.subsequent_indent
+        };
+        let mut wrapped = Cow::Borrowed(&paragraph[position..position + len]);
+        if !indent.is_empty() {
+            wrapped = Cow::Owned(String::from(indent) + &wrapped);
+        }
+        if !last.penalty.is_empty() {
+            wrapped.to_mut().push_str(last.penalty);
         }
+        lines.push(wrapped);
+
+        // the whitespace is skipped even if there is a penalty
+        position += len + last.whitespace.len();
     }
+
+    return lines;
 }
 
 #[test]
-fn test_invert_2d_vec() {
-    // "horizontal" vector
-    let mut vec1 = vec![vec![1, 2, 3], vec![4, 5, 6]];
-    invert_2d_vec(&mut vec1);
-    assert_eq!(vec1, vec![vec![1, 4], vec![2, 5], vec![3, 6]]);
-
-    // "vertical" vector
-    let mut vec2 = vec![vec![1, 2], vec![3, 4], vec![5, 6]];
-    invert_2d_vec(&mut vec2);
-    assert_eq!(vec2, vec![vec![1, 3, 5], vec![2, 4, 6]]);
-
-    // "square" vector
-    let mut vec3 = vec![vec![1, 2], vec![3, 4]];
-    invert_2d_vec(&mut vec3);
-    assert_eq!(vec3, vec![vec![1, 3], vec![2, 4]]);
-
-    // empty vector
-    let mut vec4: Vec<Vec<i8>> = vec![vec![], vec![], vec![]];
-    invert_2d_vec(&mut vec4);
-    assert_eq!(vec4, Vec::<Vec<i8>>::new());
-}
-
-// swap two values in a vector
-fn swap_2d_vec<T>(v: &mut Vec<Vec<T>>, pos1: (usize, usize), pos2: (usize, usize))
-where
-    T: Default,
-{
-    let mut temp = T::default();
-    std::mem::swap(&mut v[pos1.0][pos1.1], &mut temp);
-    std::mem::swap(&mut v[pos2.0][pos2.1], &mut temp);
-    std::mem::swap(&mut v[pos1.0][pos1.1], &mut temp);
+fn test_wrap_paragraphs() {
+    let text = "one two three\nfour\n\nfive six";
+    let options = textwrap::Options::new(8)
+        .initial_indent("* ")
+        .subsequent_indent("  ");
+
+    let wrapped = wrap_paragraphs(text, &options, &crate::UnicodeWidth);
+
+    // the same lines as `wrap`
+    let lines: Vec<&str> = wrapped.iter().map(|(line, _)| line.as_ref()).collect();
+    assert_eq!(lines, textwrap::wrap(text, &options));
+
+    let ends: Vec<bool> = wrapped.iter().map(|(_, end)| *end).collect();
+    assert_eq!(ends, vec![false, false, true, true, true, false, true]);
+}
+
+// a small pseudo-random number generator (SplitMix64),
+// so that the same seed always gives the same output
+pub fn pseudo_random(state: &mut u64) -> u64 {
+    *state = state.wrapping_add(0x9e3779b97f4a7c15);
+    let mut z = *state;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    return z ^ (z >> 31);
+}
+
+#[test]
+fn test_pseudo_random() {
+    let mut first = 42;
+    let mut second = 42;
+    let sequence: Vec<u64> = (0..5).map(|_| pseudo_random(&mut first)).collect();
+
+    // reproducible
+    assert_eq!(
+        sequence,
+        (0..5)
+            .map(|_| pseudo_random(&mut second))
+            .collect::<Vec<u64>>()
+    );
+    // not constant
+    assert_ne!(sequence[0], sequence[1]);
 }
 
 // space to allocate for spacing
-pub fn spacing_needed(spacing: crate::Spacing, columns: usize) -> usize {
-    match spacing {
-        crate::Spacing::NONE => 0,
-        crate::Spacing::BETWEEN => {
-            if columns == 0 {
-                0
-            } else {
-                columns - 1
-            }
-        }
-        crate::Spacing::AROUND => columns + 1,
+pub fn spacing_needed(
+    gutters: &crate::Gutters,
+    columns: usize,
+    measure: &dyn crate::WidthMeasure,
+) -> usize {
+    let [left, between, right] = gutters.margins();
+    if columns == 0 {
+        // the outer margins meet, leaving a single gap
+        return std::cmp::max(
+            crate::ansi::display_width(left, measure),
+            crate::ansi::display_width(right, measure),
+        );
     }
+    return crate::ansi::display_width(left, measure)
+        + crate::ansi::display_width(between, measure) * (columns - 1)
+        + crate::ansi::display_width(right, measure);
 }
 
 #[test]
 fn test_spacing_needed() {
-    assert_eq!(spacing_needed(crate::Spacing::NONE, 5), 0);
-    assert_eq!(spacing_needed(crate::Spacing::NONE, 46), 0);
+    let none = crate::Spacing::NONE.into();
+    assert_eq!(spacing_needed(&none, 5, &crate::UnicodeWidth), 0);
+    assert_eq!(spacing_needed(&none, 46, &crate::UnicodeWidth), 0);
+
+    let between = crate::Spacing::BETWEEN.into();
+    assert_eq!(spacing_needed(&between, 5, &crate::UnicodeWidth), 4);
+    assert_eq!(spacing_needed(&between, 46, &crate::UnicodeWidth), 45);
+    assert_eq!(spacing_needed(&between, 0, &crate::UnicodeWidth), 0);
+
+    let around = crate::Spacing::AROUND.into();
+    assert_eq!(spacing_needed(&around, 5, &crate::UnicodeWidth), 6);
+    assert_eq!(spacing_needed(&around, 46, &crate::UnicodeWidth), 47);
+    assert_eq!(spacing_needed(&around, 0, &crate::UnicodeWidth), 1);
+
+    let lines = crate::Gutters::new().left("│ ").between(" │ ").right(" │");
+    assert_eq!(spacing_needed(&lines, 1, &crate::UnicodeWidth), 4);
+    assert_eq!(spacing_needed(&lines, 3, &crate::UnicodeWidth), 10);
+    assert_eq!(spacing_needed(&lines, 0, &crate::UnicodeWidth), 2);
 
-    assert_eq!(spacing_needed(crate::Spacing::BETWEEN, 5), 4);
-    assert_eq!(spacing_needed(crate::Spacing::BETWEEN, 46), 45);
-    assert_eq!(spacing_needed(crate::Spacing::BETWEEN, 0), 0);
+    // escape sequences don't count
+    let colored = crate::Gutters::new().between("\x1b[2m | \x1b[0m");
+    assert_eq!(spacing_needed(&colored, 2, &crate::UnicodeWidth), 3);
 
-    assert_eq!(spacing_needed(crate::Spacing::AROUND, 5), 6);
-    assert_eq!(spacing_needed(crate::Spacing::AROUND, 46), 47);
-    assert_eq!(spacing_needed(crate::Spacing::AROUND, 0), 1);
+    // ambiguous characters may be wide
+    let dotted = crate::Gutters::new().between(" · ");
+    assert_eq!(spacing_needed(&dotted, 2, &crate::UnicodeWidth), 3);
+    assert_eq!(spacing_needed(&dotted, 2, &crate::CjkWidth), 4);
 }